@@ -0,0 +1,176 @@
+use std::fmt;
+
+use crate::grid::MAX_CELLS;
+use crate::squares::SquareFamily;
+
+/**
+ * The runtime description of the grid being searched.
+ * Replaces the old GRID_LENGTH & GRID_SIZE constants, so that any board may be solved by the same binary.
+ * Boards may be rectangular: height rows of width cells, numbered row by row.
+ * The family says which squares must be avoided; the axis-parallel squares of A227133, unless chosen otherwise.
+ * On a torus the squares may also wrap across the edges of the board.
+ * A solid board stacks depth such layers; its cells are numbered layer by layer, & within a layer row by row.
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Board {
+    pub height: u32,
+    pub width: u32,
+    /**
+     * The number of layers; 1 for a plane board.
+     */
+    pub depth: u32,
+    pub size: u32,
+    pub family: SquareFamily,
+    pub topology: Topology,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Topology {
+    #[default]
+    Plane,
+    /**
+     * The last row is followed by the first, & the last column by the first.
+     */
+    Torus,
+}
+
+impl Board {
+    /**
+     * The square board with side length.
+     */
+    pub fn new(length: u32) -> Board {
+        Board::rectangle(length, length)
+    }
+
+    pub fn rectangle(height: u32, width: u32) -> Board {
+        Board::cuboid(height, width, 1)
+    }
+
+    /**
+     * depth layers of height rows of width cells.
+     */
+    pub fn cuboid(height: u32, width: u32, depth: u32) -> Board {
+        Board {
+            height,
+            width,
+            depth,
+            size: height * width * depth,
+            family: SquareFamily::AxisParallel,
+            topology: Topology::Plane,
+        }
+    }
+
+    pub fn with_family(self, family: SquareFamily) -> Board {
+        Board { family, ..self }
+    }
+
+    pub fn with_topology(self, topology: Topology) -> Board {
+        Board { topology, ..self }
+    }
+
+    /**
+     * Parse a board from the command line: a side n, <m>x<n> for m rows of n cells, or <m>x<n>x<l> for l layers of those.
+     */
+    pub fn parse(argument: &str) -> Result<Board, String> {
+        let sides: Result<Vec<u32>, _> = argument.split('x').map(str::parse::<u32>).collect();
+        let (height, width, depth) = match sides.as_deref() {
+            Ok(&[length]) => (length, length, 1),
+            Ok(&[height, width]) => (height, width, 1),
+            Ok(&[height, width, depth]) => (height, width, depth),
+            _ => (0, 0, 0),
+        };
+        let size = height
+            .checked_mul(width)
+            .and_then(|size| size.checked_mul(depth));
+        match size {
+            Some(size) if (1..=MAX_CELLS).contains(&size) => {
+                Ok(Board::cuboid(height, width, depth))
+            }
+            _ => Err(format!(
+                "Invalid board '{}': expected n, <m>x<n> or <m>x<n>x<l>, with every side >= 1 & at most {} cells.",
+                argument, MAX_CELLS
+            )),
+        }
+    }
+
+    /**
+     * Whether the squares of the family are defined on the board.
+     * The cubes need layers; & a solid board only has the axis-parallel squares (in each coordinate plane) or cubes,
+     * without wrapping.
+     */
+    pub fn validate(&self) -> Result<(), String> {
+        match (&self.family, self.is_solid()) {
+            (SquareFamily::Cubes, false) => {
+                Err("The cubes need a solid board: <m>x<n>x<l>, with l >= 2.".to_string())
+            }
+            (SquareFamily::AllOrientations | SquareFamily::Pattern(_), true) => Err(format!(
+                "A solid board only takes the axis-parallel squares or the cubes, not {}.",
+                self.family
+            )),
+            _ if self.is_solid() && self.topology == Topology::Torus => {
+                Err("A solid board cannot be a torus.".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /**
+     * Whether the board has more than one layer.
+     */
+    pub fn is_solid(&self) -> bool {
+        self.depth > 1
+    }
+
+    /**
+     * The (layer, row, column) of a cell.
+     */
+    pub fn coordinates(&self, cell: u32) -> [u32; 3] {
+        let layer_size = self.height * self.width;
+        [
+            cell / layer_size,
+            cell % layer_size / self.width,
+            cell % self.width,
+        ]
+    }
+
+    /**
+     * The cell at a (layer, row, column).
+     */
+    pub fn cell(&self, [layer, row, column]: [u32; 3]) -> u32 {
+        (layer * self.height + row) * self.width + column
+    }
+
+    pub fn is_square(&self) -> bool {
+        self.height == self.width
+    }
+
+    /**
+     * The side of the largest square that fits on the board.
+     */
+    pub fn shorter_side(&self) -> u32 {
+        self.height.min(self.width)
+    }
+
+    /**
+     * It is impossible for a grid to contain > (size - shorter side + 1) number of 1 tiles;
+     * since there would be too few 0 tiles to fill the squares that form along a diagonal.
+     */
+    pub fn solution_is_possible_depth(&self) -> u32 {
+        self.size - self.shorter_side() + 1
+    }
+}
+
+/**
+ * The arguments of a(..) for the board: "n" for a square board, "m, n" for an m×n one, or "m, n, l" for a solid one.
+ */
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_solid() {
+            write!(f, "{}, {}, {}", self.height, self.width, self.depth)
+        } else if self.is_square() {
+            write!(f, "{}", self.width)
+        } else {
+            write!(f, "{}, {}", self.height, self.width)
+        }
+    }
+}
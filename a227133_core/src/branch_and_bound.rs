@@ -0,0 +1,109 @@
+/**
+ * An exact depth-first branch-and-bound solver.
+ *
+ * Rather than sweeping every grid of a popcount, the cells are decided one at a time in index order;
+ * each is first painted (the 1 tile) & then cleared (the 0 tile).
+ *
+ * A square is complete once its highest corner is decided, so painting a cell is forbidden
+ * as soon as it would be the last painted corner of any square: the forced square prunes that branch.
+ * The painted cells are mirrored in a Violations; so that is a lookup of the cell's completions, not a scan.
+ *
+ * The search is bounded by the best grid found so far: a branch is abandoned once its painted cells,
+ * plus an upper bound on how many of the undecided cells could still be painted, cannot beat it.
+ */
+use crate::board::Board;
+use crate::grid::Grid;
+use crate::packing::packing_bound;
+use crate::squares::get_squares;
+use crate::violations::Violations;
+
+/**
+ * Solve a(..) for the board.
+ * Has the same interface as the other solvers, so that they can be benchmarked against one another.
+ */
+pub fn solve<const W: usize>(board: &Board) -> Grid<W> {
+    solve_from(board, Grid::empty())
+}
+
+/**
+ * Like solve, but warm-started: only a grid that beats the incumbent is searched for.
+ * The incumbent must be square-free; it is returned if nothing beats it.
+ */
+pub fn solve_from<const W: usize>(board: &Board, incumbent: Grid<W>) -> Grid<W> {
+    let mut branch_and_bound = BranchAndBound::new(board, get_squares(board), incumbent);
+    branch_and_bound.branch(0, Grid::empty());
+    branch_and_bound.best
+}
+
+struct BranchAndBound<const W: usize> {
+    size: u32,
+    squares: Vec<Grid<W>>,
+    /**
+     * The counts of the painted cells of the current branch.
+     * Only cells before the one being decided are painted, so any square that it would complete has it as
+     * its highest corner.
+     */
+    violations: Violations<W>,
+    /**
+     * undecided[n] are the cells that are not yet decided when cell n is being decided.
+     */
+    undecided: Vec<Grid<W>>,
+    best: Grid<W>,
+    best_count: u32,
+}
+
+impl<const W: usize> BranchAndBound<W> {
+    fn new(board: &Board, squares: Vec<Grid<W>>, incumbent: Grid<W>) -> BranchAndBound<W> {
+        let all_cells: Grid<W> = Grid::with_lowest_bits(board.size);
+        let undecided = (0..=board.size)
+            .map(|cell| all_cells & !Grid::with_lowest_bits(cell))
+            .collect();
+
+        BranchAndBound {
+            size: board.size,
+            violations: Violations::new(board, &squares),
+            squares,
+            undecided,
+            best: incumbent,
+            best_count: incumbent.count_ones(),
+        }
+    }
+
+    /**
+     * Decide cell, given the painted cells out of those before it.
+     */
+    fn branch(&mut self, cell: u32, painted: Grid<W>) {
+        if cell == self.size {
+            if painted.count_ones() > self.best_count {
+                self.best = painted;
+                self.best_count = painted.count_ones();
+            }
+            return;
+        }
+
+        if painted.count_ones() + self.remaining_upper_bound(cell, &painted) <= self.best_count {
+            return;
+        }
+
+        // Paint the cell; unless it is the final corner of a square:
+        if self.violations.completions(cell) == 0 {
+            self.violations.paint(cell);
+            self.branch(cell + 1, painted | Grid::from_bit(cell));
+            self.violations.clear(cell);
+        }
+
+        // Clear the cell:
+        self.branch(cell + 1, painted);
+    }
+
+    /**
+     * An upper bound on how many of the cells from cell onwards can still be painted, on this branch:
+     * all of them, less one per packed square (see a227133_core::packing). The cells before cell that were
+     * not painted are the cleared ones, so the squares through them are already broken.
+     */
+    fn remaining_upper_bound(&self, cell: u32, painted: &Grid<W>) -> u32 {
+        let undecided = self.undecided[cell as usize];
+        let cleared = !(undecided | *painted);
+        undecided.count_ones() - packing_bound(&self.squares, &cleared, &undecided)
+    }
+}
@@ -0,0 +1,618 @@
+/**
+ * Certificates of optimality: a grid with a(n) painted cells & no square, plus a proof that no grid has a(n) + 1.
+ *
+ * The proof is a DRAT-style list of lemmas, emitted by the SAT solver; each must follow from the square clauses,
+ * the cardinality constraint ("at least k painted cells") & the lemmas before it, & the last is the empty clause:
+ *
+ * - RUP lemmas (the learnt clauses): assigning every literal false & unit propagating leads to a conflict.
+ * - Packing lemmas (the reasons of the cardinality constraint): assigning every literal false clears some cells,
+ *   & leaves the listed squares with no cleared corner & no undecided corner in common;
+ *   each still needs one more cell cleared, which is more than the cardinality constraint allows.
+ *
+ * The verifier never uses get_squares or the solvers; it regenerates the squares from their definition,
+ * so that a bug in either is not simply repeated.
+ *
+ * The text format has DIMACS literals (variable n is cell n - 1, & is positive when painted):
+ *
+ *   p a227133 <height> <width> <k>   (or p a227133 <length> <k>, for a square board)
+ *   s <square family>                (only for squares in all orientations; axis-parallel otherwise)
+ *   t torus                          (only when the squares wrap across the edges of the board)
+ *   v <painted variables> 0
+ *   j <clause> 0 <the 4 corner variables of each packed square>
+ *   <clause> 0
+ */
+use crate::board::{Board, Topology};
+use crate::grid::MAX_CELLS;
+use crate::squares::SquareFamily;
+
+use std::collections::{BTreeSet, HashSet};
+
+pub enum Lemma {
+    Rup(Vec<i32>),
+    Packing {
+        clause: Vec<i32>,
+        /**
+         * The corner cells of each square in the packing.
+         */
+        squares: Vec<Vec<u32>>,
+    },
+}
+
+pub struct Certificate {
+    pub height: u32,
+    pub width: u32,
+    pub family: SquareFamily,
+    pub topology: Topology,
+    /**
+     * The popcount that is proven impossible; one more than the solution's.
+     */
+    pub k: u32,
+    /**
+     * The painted cells of a grid with k - 1 painted cells & no square.
+     */
+    pub solution: Vec<u32>,
+    pub lemmas: Vec<Lemma>,
+}
+
+impl Certificate {
+    /**
+     * The board that the certificate is for; always a single layer, as only plane boards are certified.
+     */
+    pub fn board(&self) -> Board {
+        Board::rectangle(self.height, self.width)
+            .with_family(self.family.clone())
+            .with_topology(self.topology)
+    }
+
+    pub fn to_text(&self) -> String {
+        let dimensions = if self.height == self.width {
+            format!("{}", self.width)
+        } else {
+            format!("{} {}", self.height, self.width)
+        };
+        let mut text = format!(
+            "c A227133 certificate: F({}) = {}\np a227133 {} {}\n",
+            dimensions.replace(' ', ", "),
+            self.k - 1,
+            dimensions,
+            self.k
+        );
+        if self.family != SquareFamily::AxisParallel {
+            text += &format!("s {}\n", self.family);
+        }
+        if self.topology == Topology::Torus {
+            text += "t torus\n";
+        }
+        text += "v";
+        for cell in self.solution.iter() {
+            text += &format!(" {}", cell + 1);
+        }
+        text += " 0\n";
+
+        for lemma in self.lemmas.iter() {
+            let (prefix, clause, squares): (&str, &Vec<i32>, &[Vec<u32>]) = match lemma {
+                Lemma::Rup(clause) => ("", clause, &[]),
+                Lemma::Packing { clause, squares } => ("j ", clause, squares),
+            };
+            text += prefix;
+            for literal in clause.iter() {
+                text += &format!("{} ", literal);
+            }
+            text += "0";
+            for corner in squares.iter().flatten() {
+                text += &format!(" {}", corner + 1);
+            }
+            text += "\n";
+        }
+        text
+    }
+
+    pub fn read(text: &str) -> Result<Certificate, String> {
+        let mut header: Option<(u32, u32, u32)> = None;
+        let mut family = SquareFamily::AxisParallel;
+        let mut topology = Topology::Plane;
+        let mut solution: Option<Vec<u32>> = None;
+        let mut lemmas: Vec<Lemma> = Vec::new();
+
+        for (line_index, line) in text.lines().map(str::trim).enumerate() {
+            let invalid = |reason: &str| format!("Line {}: {}", line_index + 1, reason);
+            if line.is_empty() || line.starts_with('c') {
+                continue;
+            }
+
+            let mut tokens = line.split_whitespace();
+            let first = tokens.clone().next().unwrap();
+            let numbers = |tokens: std::str::SplitWhitespace| -> Result<Vec<i32>, String> {
+                tokens
+                    .map(|token| {
+                        token
+                            .parse::<i32>()
+                            .map_err(|_| invalid(&format!("invalid number '{}'.", token)))
+                    })
+                    .collect()
+            };
+            match first {
+                "p" => {
+                    let fields: Vec<&str> = tokens.collect();
+                    let side = |side: &str| side.parse().map_err(|_| invalid("invalid side."));
+                    let k = |k: &str| k.parse().map_err(|_| invalid("invalid k."));
+                    header = match fields[..] {
+                        ["p", "a227133", length, k_field] => {
+                            Some((side(length)?, side(length)?, k(k_field)?))
+                        }
+                        ["p", "a227133", height, width, k_field] => {
+                            Some((side(height)?, side(width)?, k(k_field)?))
+                        }
+                        _ => return Err(invalid("expected 'p a227133 <height> <width> <k>'.")),
+                    };
+                }
+                "s" => {
+                    let fields: Vec<&str> = tokens.collect();
+                    family = match fields[..] {
+                        ["s", name] => {
+                            SquareFamily::parse(name).map_err(|error| invalid(&error))?
+                        }
+                        _ => return Err(invalid("expected 's <square family>'.")),
+                    };
+                }
+                "t" => {
+                    let fields: Vec<&str> = tokens.collect();
+                    topology = match fields[..] {
+                        ["t", "torus"] => Topology::Torus,
+                        _ => return Err(invalid("expected 't torus'.")),
+                    };
+                }
+                "v" => {
+                    tokens.next();
+                    let variables = numbers(tokens)?;
+                    let (end, painted) = variables.split_last().unwrap_or((&-1, &[]));
+                    if *end != 0 || painted.iter().any(|&variable| variable < 1) {
+                        return Err(invalid("expected the painted variables, ending with 0."));
+                    }
+                    solution = Some(
+                        painted
+                            .iter()
+                            .map(|&variable| variable as u32 - 1)
+                            .collect(),
+                    );
+                }
+                _ => {
+                    let is_packing = first == "j";
+                    if is_packing {
+                        tokens.next();
+                    }
+                    let numbers = numbers(tokens)?;
+                    let end = numbers
+                        .iter()
+                        .position(|&literal| literal == 0)
+                        .ok_or_else(|| invalid("the clause does not end with 0."))?;
+                    let clause = numbers[..end].to_vec();
+                    let corners = &numbers[end + 1..];
+
+                    if !is_packing {
+                        if !corners.is_empty() {
+                            return Err(invalid("a RUP lemma has nothing after its 0."));
+                        }
+                        lemmas.push(Lemma::Rup(clause));
+                        continue;
+                    }
+                    if corners.len() % 4 != 0 || corners.iter().any(|&corner| corner < 1) {
+                        return Err(invalid("squares are given by their 4 corner variables."));
+                    }
+                    let squares = corners
+                        .chunks(4)
+                        .map(|square| square.iter().map(|&corner| corner as u32 - 1).collect())
+                        .collect();
+                    lemmas.push(Lemma::Packing { clause, squares });
+                }
+            }
+        }
+
+        let (height, width, k) = header.ok_or("The certificate has no 'p' line.")?;
+        Ok(Certificate {
+            height,
+            width,
+            family,
+            topology,
+            k,
+            solution: solution.ok_or("The certificate has no 'v' line.")?,
+            lemmas,
+        })
+    }
+
+    /**
+     * Check the solution & every lemma; returns a(n) once the empty clause is reached.
+     */
+    pub fn verify(&self) -> Result<u32, String> {
+        if let SquareFamily::Pattern(_) | SquareFamily::Cubes = self.family {
+            return Err(
+                "Certificates only cover the squares of plane boards, not patterns or cubes."
+                    .to_string(),
+            );
+        }
+        let size = self
+            .height
+            .checked_mul(self.width)
+            .filter(|&size| size <= MAX_CELLS)
+            .ok_or_else(|| {
+                format!(
+                    "A {}×{} board is larger than the {} cells that are supported.",
+                    self.height, self.width, MAX_CELLS
+                )
+            })?;
+        let squares = regenerate_squares(self.height, self.width, &self.family, self.topology);
+        let square_set: HashSet<Vec<u32>> = squares.iter().map(|square| square.to_vec()).collect();
+
+        // The solution:
+        let painted: HashSet<u32> = self.solution.iter().copied().collect();
+        if painted.len() != self.solution.len() || painted.iter().any(|&cell| cell >= size) {
+            return Err("The solution's cells are repeated or off the grid.".to_string());
+        }
+        if painted.len() as u32 + 1 != self.k {
+            return Err(format!(
+                "The solution paints {} cells, but the proof is for {}.",
+                painted.len(),
+                self.k
+            ));
+        }
+        if let Some(square) = squares
+            .iter()
+            .find(|square| square.iter().all(|corner| painted.contains(corner)))
+        {
+            return Err(format!(
+                "The solution contains the square with corners {:?}.",
+                square
+            ));
+        }
+
+        // The proof:
+        let max_cleared = size as i64 - self.k as i64;
+        let mut checker = RupChecker::new(size as usize);
+        for square in squares.iter() {
+            checker.add(square.iter().map(|&corner| -(corner as i32 + 1)).collect());
+        }
+
+        for (lemma_index, lemma) in self.lemmas.iter().enumerate() {
+            let invalid = |reason: String| format!("Lemma {}: {}", lemma_index + 1, reason);
+            let clause = match lemma {
+                Lemma::Rup(clause) | Lemma::Packing { clause, .. } => clause,
+            };
+            if clause
+                .iter()
+                .any(|&literal| literal == 0 || literal.unsigned_abs() > size)
+            {
+                return Err(invalid("a literal is not a cell.".to_string()));
+            }
+
+            match lemma {
+                Lemma::Rup(clause) => {
+                    if !checker.is_rup(clause) {
+                        return Err(invalid(
+                            "unit propagation does not reach a conflict.".to_string(),
+                        ));
+                    }
+                }
+                Lemma::Packing { clause, squares } => {
+                    check_packing(clause, squares, &square_set, max_cleared).map_err(invalid)?;
+                }
+            }
+            if clause.is_empty() {
+                return Ok(self.k - 1);
+            }
+            checker.add(clause.clone());
+        }
+        Err("The proof does not reach the empty clause.".to_string())
+    }
+}
+
+/**
+ * Every square on the grid, as its corner cells in ascending order; straight from the definition.
+ *
+ * Each pair of cells is taken as one side of a square, & the side is turned through a right angle to find the
+ * other 2 corners; the axis-parallel family only keeps the squares whose sides are along a row or column.
+ * On a torus, each of those squares is then moved to every position, wrapping across the edges.
+ */
+fn regenerate_squares(
+    height: u32,
+    width: u32,
+    family: &SquareFamily,
+    topology: Topology,
+) -> Vec<[u32; 4]> {
+    let (height, width) = (height as i64, width as i64);
+    let on_board =
+        |&(row, column): &(i64, i64)| (0..height).contains(&row) && (0..width).contains(&column);
+    let cells = || (0..height).flat_map(move |row| (0..width).map(move |column| (row, column)));
+
+    let mut squares: BTreeSet<[u32; 4]> = BTreeSet::new();
+    for (row, column) in cells() {
+        for (other_row, other_column) in cells() {
+            let (down, across) = (other_row - row, other_column - column);
+            if (down, across) == (0, 0)
+                || (*family == SquareFamily::AxisParallel && down != 0 && across != 0)
+            {
+                continue;
+            }
+            let corners = [
+                (row, column),
+                (other_row, other_column),
+                (other_row + across, other_column - down),
+                (row + across, column - down),
+            ];
+            if corners.iter().all(on_board) {
+                let mut square = corners.map(|(row, column)| (row * width + column) as u32);
+                square.sort();
+                squares.insert(square);
+            }
+        }
+    }
+
+    if topology == Topology::Torus {
+        let mut wrapped: BTreeSet<[u32; 4]> = BTreeSet::new();
+        for square in squares.iter() {
+            for (down, across) in cells() {
+                let mut image = square.map(|corner| {
+                    let (row, column) = (corner as i64 / width, corner as i64 % width);
+                    ((row + down) % height * width + (column + across) % width) as u32
+                });
+                image.sort();
+                wrapped.insert(image);
+            }
+        }
+        squares = wrapped;
+    }
+    squares.into_iter().collect()
+}
+
+/**
+ * With every literal of the clause false, some cells are cleared & some painted.
+ * The squares must be real, with no cleared corner & some undecided corners, none of which they share;
+ * so that each needs one more cell cleared, which must be too many.
+ */
+fn check_packing(
+    clause: &[i32],
+    squares: &[Vec<u32>],
+    square_set: &HashSet<Vec<u32>>,
+    max_cleared: i64,
+) -> Result<(), String> {
+    let cleared: HashSet<u32> = clause
+        .iter()
+        .filter(|&&literal| literal > 0)
+        .map(|&literal| literal as u32 - 1)
+        .collect();
+    let painted: HashSet<u32> = clause
+        .iter()
+        .filter(|&&literal| literal < 0)
+        .map(|&literal| literal.unsigned_abs() - 1)
+        .collect();
+    if !cleared.is_disjoint(&painted) {
+        return Err("the clause is a tautology.".to_string());
+    }
+
+    let mut packed_cells: HashSet<u32> = HashSet::new();
+    for square in squares.iter() {
+        let mut sorted = square.clone();
+        sorted.sort_unstable();
+        if !square_set.contains(&sorted) {
+            return Err(format!("{:?} is not a square.", square));
+        }
+        if square.iter().any(|corner| cleared.contains(corner)) {
+            return Err(format!("{:?} has a cleared corner.", square));
+        }
+        let undecided: Vec<u32> = square
+            .iter()
+            .copied()
+            .filter(|corner| !painted.contains(corner))
+            .collect();
+        if undecided.is_empty() {
+            return Err(format!("{:?} has no undecided corner.", square));
+        }
+        for corner in undecided {
+            if !packed_cells.insert(corner) {
+                return Err(format!("{:?} shares an undecided corner.", square));
+            }
+        }
+    }
+
+    if cleared.len() as i64 + squares.len() as i64 <= max_cleared {
+        return Err(format!(
+            "{} cleared cells & {} squares are within the {} that may be cleared.",
+            cleared.len(),
+            squares.len(),
+            max_cleared
+        ));
+    }
+    Ok(())
+}
+
+//-------------
+// RUP Checker:
+//-------------
+
+/**
+ * Unit propagation over a growing set of clauses, via two watched literals.
+ * Literals are DIMACS; the watches of literal l are at index 2 * (|l| - 1) + (l < 0).
+ */
+struct RupChecker {
+    clauses: Vec<Vec<i32>>,
+    watches: Vec<Vec<usize>>,
+    units: Vec<i32>,
+    /**
+     * values[v - 1] is 1 when variable v is true, -1 when false & 0 when unassigned.
+     */
+    values: Vec<i8>,
+    trail: Vec<i32>,
+}
+
+fn watch_index(literal: i32) -> usize {
+    2 * (literal.unsigned_abs() as usize - 1) + (literal < 0) as usize
+}
+
+impl RupChecker {
+    fn new(variable_qty: usize) -> RupChecker {
+        RupChecker {
+            clauses: Vec::new(),
+            watches: vec![Vec::new(); 2 * variable_qty],
+            units: Vec::new(),
+            values: vec![0; variable_qty],
+            trail: Vec::new(),
+        }
+    }
+
+    fn value(&self, literal: i32) -> i8 {
+        let value = self.values[literal.unsigned_abs() as usize - 1];
+        if literal < 0 {
+            -value
+        } else {
+            value
+        }
+    }
+
+    fn add(&mut self, clause: Vec<i32>) {
+        match clause.len() {
+            0 => {}
+            1 => self.units.push(clause[0]),
+            _ => {
+                let index = self.clauses.len();
+                self.watches[watch_index(clause[0])].push(index);
+                self.watches[watch_index(clause[1])].push(index);
+                self.clauses.push(clause);
+            }
+        }
+    }
+
+    /**
+     * Assign the literal true; false if it is already false.
+     */
+    fn assign(&mut self, literal: i32) -> bool {
+        match self.value(literal) {
+            1 => true,
+            -1 => false,
+            _ => {
+                self.values[literal.unsigned_abs() as usize - 1] = if literal < 0 { -1 } else { 1 };
+                self.trail.push(literal);
+                true
+            }
+        }
+    }
+
+    /**
+     * Is there a conflict by unit propagation, once every literal of the clause is false?
+     */
+    fn is_rup(&mut self, clause: &[i32]) -> bool {
+        let units = self.units.clone();
+        let conflict = !units.iter().all(|&unit| self.assign(unit))
+            || !clause.iter().all(|&literal| self.assign(-literal))
+            || self.propagate();
+
+        for &literal in self.trail.iter() {
+            self.values[literal.unsigned_abs() as usize - 1] = 0;
+        }
+        self.trail.clear();
+        conflict
+    }
+
+    /**
+     * Propagate the trail; true on a conflict.
+     */
+    fn propagate(&mut self) -> bool {
+        let mut propagated = 0;
+        while propagated < self.trail.len() {
+            let false_literal = -self.trail[propagated];
+            propagated += 1;
+
+            let watching = std::mem::take(&mut self.watches[watch_index(false_literal)]);
+            let mut kept = Vec::with_capacity(watching.len());
+            let mut conflict = false;
+
+            for (position, &index) in watching.iter().enumerate() {
+                if conflict {
+                    kept.extend_from_slice(&watching[position..]);
+                    break;
+                }
+                let clause = &mut self.clauses[index];
+                if clause[0] == false_literal {
+                    clause.swap(0, 1);
+                }
+                let first = clause[0];
+                let clause = &self.clauses[index];
+                let replacement =
+                    (2..clause.len()).find(|&position| self.value(clause[position]) != -1);
+
+                if self.value(first) == 1 {
+                    kept.push(index);
+                } else if let Some(replacement) = replacement {
+                    let clause = &mut self.clauses[index];
+                    clause.swap(1, replacement);
+                    self.watches[watch_index(clause[1])].push(index);
+                } else {
+                    kept.push(index);
+                    conflict = !self.assign(first);
+                }
+            }
+
+            self.watches[watch_index(false_literal)] = kept;
+            if conflict {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sat::solve_with_certificate;
+
+    /**
+     * The certificate of the board, as written to & read back from its file.
+     */
+    fn certificate(board: &Board) -> Certificate {
+        let (_, certificate) = solve_with_certificate::<1>(board).unwrap();
+        Certificate::read(&certificate.to_text()).unwrap()
+    }
+
+    #[test]
+    fn solver_certificates_verify() {
+        for (board, term) in [
+            (Board::new(5), 17),
+            (Board::rectangle(3, 5), 11),
+            (Board::new(5).with_topology(Topology::Torus), 16),
+            (Board::new(5).with_family(SquareFamily::AllOrientations), 15),
+        ] {
+            assert_eq!(certificate(&board).verify(), Ok(term), "{:?}", board);
+        }
+    }
+
+    #[test]
+    fn tampered_certificates_are_rejected() {
+        let board = Board::new(5);
+
+        let mut dropped = certificate(&board);
+        dropped.lemmas.pop();
+        assert!(dropped.verify().is_err());
+
+        let mut flipped = certificate(&board);
+        let (Lemma::Rup(clause) | Lemma::Packing { clause, .. }) = &mut flipped.lemmas[0];
+        clause[0] = -clause[0];
+        assert!(flipped.verify().is_err());
+
+        for k in [16, 19] {
+            let mut wrong_k = certificate(&board);
+            wrong_k.k = k;
+            assert!(wrong_k.verify().is_err(), "k = {}", k);
+        }
+
+        let mut wrong_board = certificate(&board);
+        wrong_board.width = 6;
+        assert!(wrong_board.verify().is_err());
+
+        let mut torus = certificate(&board);
+        torus.topology = Topology::Torus;
+        assert!(torus.verify().is_err());
+
+        let mut oversized = certificate(&board);
+        (oversized.height, oversized.width) = (1 << 16, 1 << 16);
+        assert!(oversized.verify().is_err());
+    }
+}
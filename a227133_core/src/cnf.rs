@@ -0,0 +1,231 @@
+/**
+ * Export of the problem as DIMACS CNF, for cross-checking terms with external SAT solvers.
+ *
+ * Variable n (1-based) is true when cell n - 1 is painted. The formula is satisfiable
+ * exactly when there is a grid with at least k painted cells that contains no square:
+ *
+ * - Each square becomes the 4-literal clause (-a -b -c -d): not all of its corners may be painted.
+ * - At least k cells painted is at most size - k cells cleared; encoded as a sequential counter
+ *   (Sinz, 2005) over the cleared literals, whose auxiliary variables follow the cell variables.
+ */
+use crate::board::Board;
+use crate::grid::Grid;
+use crate::squares::grid_contains_squares;
+
+pub struct Cnf {
+    pub variable_qty: u32,
+    pub clauses: Vec<Vec<i32>>,
+}
+
+impl Cnf {
+    pub fn to_dimacs(&self) -> String {
+        let mut dimacs = format!("p cnf {} {}\n", self.variable_qty, self.clauses.len());
+        for clause in self.clauses.iter() {
+            for literal in clause.iter() {
+                dimacs += &format!("{} ", literal);
+            }
+            dimacs += "0\n";
+        }
+        dimacs
+    }
+}
+
+/**
+ * The CNF for "at least k painted cells & no square".
+ */
+pub fn encode<const W: usize>(board: &Board, squares: &[Grid<W>], k: u32) -> Cnf {
+    let cell_variable = |cell: u32| (cell + 1) as i32;
+
+    let mut clauses: Vec<Vec<i32>> = squares
+        .iter()
+        .map(|square| square.ones().map(|cell| -cell_variable(cell)).collect())
+        .collect();
+
+    let cleared: Vec<i32> = (0..board.size).map(|cell| -cell_variable(cell)).collect();
+    let mut variable_qty = board.size;
+    if k > board.size {
+        // More cells than the grid has; unsatisfiable:
+        clauses.push(Vec::new());
+    } else {
+        at_most(&cleared, board.size - k, &mut variable_qty, &mut clauses);
+    }
+
+    Cnf {
+        variable_qty,
+        clauses,
+    }
+}
+
+/**
+ * Sequential counter encoding of "at most bound of the literals are true".
+ * Auxiliary variable s(i, j) is true when at least j of the first i + 1 literals are true.
+ */
+fn at_most(literals: &[i32], bound: u32, variable_qty: &mut u32, clauses: &mut Vec<Vec<i32>>) {
+    let n = literals.len();
+    let bound = bound as usize;
+    if bound >= n {
+        return;
+    }
+    if bound == 0 {
+        clauses.extend(literals.iter().map(|&literal| vec![-literal]));
+        return;
+    }
+
+    // s(i, j) for i in 0..n - 1 & j in 1..=bound:
+    let first_auxiliary = *variable_qty as i32 + 1;
+    let s = |i: usize, j: usize| first_auxiliary + (i * bound + (j - 1)) as i32;
+    *variable_qty += ((n - 1) * bound) as u32;
+
+    clauses.push(vec![-literals[0], s(0, 1)]);
+    for j in 2..=bound {
+        clauses.push(vec![-s(0, j)]);
+    }
+    for (i, &literal) in literals.iter().enumerate().take(n - 1).skip(1) {
+        clauses.push(vec![-literal, s(i, 1)]);
+        clauses.push(vec![-s(i - 1, 1), s(i, 1)]);
+        for j in 2..=bound {
+            clauses.push(vec![-literal, -s(i - 1, j - 1), s(i, j)]);
+            clauses.push(vec![-s(i - 1, j), s(i, j)]);
+        }
+        clauses.push(vec![-literal, -s(i - 1, bound)]);
+    }
+    clauses.push(vec![-literals[n - 1], -s(n - 2, bound)]);
+}
+
+//----------------
+// Model Checking:
+//----------------
+
+/**
+ * Read the grid out of a SAT solver's model.
+ *
+ * Accepts the SAT competition format ("s SATISFIABLE" followed by "v" lines),
+ * as well as the bare list of literals written by solvers such as MiniSat.
+ * Literals of the auxiliary variables are ignored.
+ */
+pub fn read_model<const W: usize>(board: &Board, model: &str) -> Result<Grid<W>, String> {
+    let mut grid = Grid::empty();
+
+    for line in model.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('c') {
+            continue;
+        }
+        if line.contains("UNSAT") {
+            return Err("The model reports the formula as unsatisfiable.".to_string());
+        }
+        if line.starts_with('s') || line == "SAT" {
+            continue;
+        }
+
+        let literals = line.strip_prefix('v').unwrap_or(line);
+        for literal in literals.split_whitespace() {
+            let literal: i64 = literal
+                .parse()
+                .map_err(|_| format!("Invalid literal '{}' in the model.", literal))?;
+            if literal > 0 && literal <= board.size as i64 {
+                grid.set(literal as u32 - 1);
+            }
+        }
+    }
+    Ok(grid)
+}
+
+/**
+ * Is the grid a witness for "at least k painted cells & no square"?
+ */
+pub fn validate_model<const W: usize>(
+    grid: Grid<W>,
+    squares: &[Grid<W>],
+    k: u32,
+) -> Result<(), String> {
+    if grid.count_ones() < k {
+        return Err(format!(
+            "The model paints {} cells; fewer than {}.",
+            grid.count_ones(),
+            k
+        ));
+    }
+    if grid_contains_squares(grid, squares) {
+        let square = squares
+            .iter()
+            .find(|square| square.is_subset_of(&grid))
+            .unwrap();
+        return Err(format!(
+            "The model contains the square with corners {:?}.",
+            square.ones().collect::<Vec<u32>>()
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::branch_and_bound;
+    use crate::squares::get_squares;
+
+    /**
+     * Does the grid, extended by the counter's least assignment, satisfy every clause?
+     * The counter's clauses only ever force an auxiliary variable true, so if the least one fails, all do.
+     */
+    fn satisfies(cnf: &Cnf, grid: &Grid<1>) -> bool {
+        let mut values = vec![false; cnf.variable_qty as usize];
+        for cell in grid.ones() {
+            values[cell as usize] = true;
+        }
+        let is_true = |values: &[bool], literal: i32| {
+            values[literal.unsigned_abs() as usize - 1] == (literal > 0)
+        };
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for clause in cnf.clauses.iter() {
+                if let [rest @ .., last] = &clause[..] {
+                    if *last > 0
+                        && !is_true(&values, *last)
+                        && rest.iter().all(|&literal| !is_true(&values, literal))
+                    {
+                        values[*last as usize - 1] = true;
+                        changed = true;
+                    }
+                }
+            }
+        }
+        cnf.clauses
+            .iter()
+            .all(|clause| clause.iter().any(|&literal| is_true(&values, literal)))
+    }
+
+    #[test]
+    fn optimal_grids_satisfy_the_cnf_and_no_more_cells_do() {
+        for length in 2..=5 {
+            let board = Board::new(length);
+            let squares: Vec<Grid<1>> = get_squares(&board);
+            let optimal: Grid<1> = branch_and_bound::solve(&board);
+            let term = optimal.count_ones();
+            let extra_cell = (0..board.size).find(|&cell| !optimal.test(cell)).unwrap();
+            let mut extra = optimal;
+            extra.set(extra_cell);
+
+            assert!(satisfies(&encode(&board, &squares, term), &optimal));
+            assert!(!satisfies(&encode(&board, &squares, term + 1), &optimal));
+            assert!(!satisfies(&encode(&board, &squares, term + 1), &extra));
+
+            // As a solver would print the model, auxiliary variables included:
+            let cnf = encode(&board, &squares, term);
+            let literals: Vec<String> = (1..=cnf.variable_qty as i32)
+                .map(|variable| {
+                    let painted =
+                        variable <= board.size as i32 && optimal.test(variable as u32 - 1);
+                    (if painted { variable } else { -variable }).to_string()
+                })
+                .collect();
+            let model = format!("s SATISFIABLE\nv {} 0\n", literals.join(" "));
+            let read: Grid<1> = read_model(&board, &model).unwrap();
+            assert_eq!(read, optimal);
+            assert!(validate_model(read, &squares, term).is_ok());
+            assert!(validate_model(read, &squares, term + 1).is_err());
+            assert!(validate_model(extra, &squares, term + 1).is_err());
+        }
+    }
+}
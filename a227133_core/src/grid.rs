@@ -0,0 +1,307 @@
+/**
+ * An arbitrary-width bitboard.
+ *
+ * A grid is stored as W 64-bit words, where bit n of the grid is bit (n % 64) of word (n / 64).
+ * This lifts the 128 cell cap of a u128, whilst keeping grid checking as a handful of word-wise ANDs.
+ *
+ * The width is a const generic so that the hot loops are monomorphised for the smallest width that fits;
+ * see words_for & with_grid_words! for picking that width at runtime.
+ */
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, Not};
+
+/**
+ * The widest supported grid, in cells.
+ * Must agree with the largest width dispatched by with_grid_words!
+ */
+pub const MAX_CELLS: u32 = 1024;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Grid<const W: usize> {
+    words: [u64; W],
+}
+
+impl<const W: usize> Grid<W> {
+    pub const BITS: u32 = 64 * W as u32;
+
+    pub const fn empty() -> Self {
+        Grid { words: [0; W] }
+    }
+
+    /**
+     * The grid with only the given cell set.
+     */
+    pub fn from_bit(index: u32) -> Self {
+        let mut grid = Self::empty();
+        grid.set(index);
+        grid
+    }
+
+    /**
+     * The grid with the lowest count cells set; the equivalent of (1 << count) - 1.
+     * This is the first permutation of a popcount in Gosper's hack.
+     */
+    pub fn with_lowest_bits(count: u32) -> Self {
+        let mut grid = Self::empty();
+        for (word_index, word) in grid.words.iter_mut().enumerate() {
+            let low = 64 * word_index as u32;
+            if count >= low + 64 {
+                *word = u64::MAX;
+            } else if count > low {
+                *word = (1 << (count - low)) - 1;
+            }
+        }
+        grid
+    }
+
+    pub fn test(&self, index: u32) -> bool {
+        (self.words[(index / 64) as usize] >> (index % 64)) & 1 == 1
+    }
+
+    pub fn set(&mut self, index: u32) {
+        self.words[(index / 64) as usize] |= 1 << (index % 64);
+    }
+
+    pub fn clear(&mut self, index: u32) {
+        self.words[(index / 64) as usize] &= !(1 << (index % 64));
+    }
+
+    pub fn count_ones(&self) -> u32 {
+        self.words.iter().map(|word| word.count_ones()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|&word| word == 0)
+    }
+
+    /**
+     * Index of the lowest set cell; Self::BITS if the grid is empty.
+     */
+    pub fn trailing_zeros(&self) -> u32 {
+        let mut zeros = 0;
+        for word in self.words.iter() {
+            if *word != 0 {
+                return zeros + word.trailing_zeros();
+            }
+            zeros += 64;
+        }
+        zeros
+    }
+
+    /**
+     * Is every set cell of self also set in other?
+     * Used to test whether a grid contains a square: square.is_subset_of(grid).
+     */
+    pub fn is_subset_of(&self, other: &Self) -> bool {
+        self.words
+            .iter()
+            .zip(other.words.iter())
+            .all(|(&word, &other_word)| word & other_word == word)
+    }
+
+    /**
+     * Iterate over the indices of the set cells, lowest first.
+     */
+    pub fn ones(&self) -> impl Iterator<Item = u32> + '_ {
+        self.words
+            .iter()
+            .enumerate()
+            .flat_map(|(word_index, &word)| {
+                let mut remaining = word;
+                std::iter::from_fn(move || {
+                    if remaining == 0 {
+                        return None;
+                    }
+                    let bit = remaining.trailing_zeros();
+                    remaining &= remaining - 1;
+                    Some(64 * word_index as u32 + bit)
+                })
+            })
+    }
+
+    /**
+     * Gosper's hack, generalised to multiple words.
+     * Generates the lexographically next grid with the same popcount.
+     * Wraps around (like the u128 version) once the highest permutation has been passed.
+     */
+    pub fn next_combination(&self) -> Self {
+        let lowest_set = self.trailing_zeros();
+        let ripple = self.wrapping_add(&Self::from_bit(lowest_set));
+        let ones = (ripple ^ *self).shr(2 + lowest_set);
+        ripple | ones
+    }
+
+    fn wrapping_add(&self, other: &Self) -> Self {
+        let mut sum = Self::empty();
+        let mut carry = false;
+        for i in 0..W {
+            let (partial, first_carry) = self.words[i].overflowing_add(other.words[i]);
+            let (total, second_carry) = partial.overflowing_add(carry as u64);
+            sum.words[i] = total;
+            carry = first_carry | second_carry;
+        }
+        sum
+    }
+
+    fn shr(&self, shift: u32) -> Self {
+        let mut shifted = Self::empty();
+        let word_shift = (shift / 64) as usize;
+        let bit_shift = shift % 64;
+        for i in 0..W.saturating_sub(word_shift) {
+            let low = self.words[i + word_shift] >> bit_shift;
+            let high = match (bit_shift, self.words.get(i + word_shift + 1)) {
+                (0, _) | (_, None) => 0,
+                (_, Some(&next)) => next << (64 - bit_shift),
+            };
+            shifted.words[i] = low | high;
+        }
+        shifted
+    }
+}
+
+impl<const W: usize> Default for Grid<W> {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+/**
+ * Grids are ordered as the integers they represent, so the highest word is the most significant.
+ */
+impl<const W: usize> Ord for Grid<W> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.words.iter().rev().cmp(other.words.iter().rev())
+    }
+}
+
+impl<const W: usize> PartialOrd for Grid<W> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<const W: usize> BitAnd for Grid<W> {
+    type Output = Self;
+
+    fn bitand(mut self, other: Self) -> Self {
+        self &= other;
+        self
+    }
+}
+
+impl<const W: usize> BitAndAssign for Grid<W> {
+    fn bitand_assign(&mut self, other: Self) {
+        for (word, other_word) in self.words.iter_mut().zip(other.words) {
+            *word &= other_word;
+        }
+    }
+}
+
+impl<const W: usize> BitOr for Grid<W> {
+    type Output = Self;
+
+    fn bitor(mut self, other: Self) -> Self {
+        self |= other;
+        self
+    }
+}
+
+impl<const W: usize> BitOrAssign for Grid<W> {
+    fn bitor_assign(&mut self, other: Self) {
+        for (word, other_word) in self.words.iter_mut().zip(other.words) {
+            *word |= other_word;
+        }
+    }
+}
+
+impl<const W: usize> BitXor for Grid<W> {
+    type Output = Self;
+
+    fn bitxor(mut self, other: Self) -> Self {
+        for (word, other_word) in self.words.iter_mut().zip(other.words) {
+            *word ^= other_word;
+        }
+        self
+    }
+}
+
+impl<const W: usize> Not for Grid<W> {
+    type Output = Self;
+
+    fn not(mut self) -> Self {
+        for word in self.words.iter_mut() {
+            *word = !*word;
+        }
+        self
+    }
+}
+
+/**
+ * Formats the grid as a binary integer; supports the same width & fill flags as the integer types.
+ */
+impl<const W: usize> fmt::Binary for Grid<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let highest = (0..Self::BITS).rev().find(|&index| self.test(index));
+        let digits: String = match highest {
+            Some(highest) => (0..=highest)
+                .rev()
+                .map(|index| if self.test(index) { '1' } else { '0' })
+                .collect(),
+            None => "0".to_string(),
+        };
+        f.pad_integral(true, "0b", &digits)
+    }
+}
+
+impl<const W: usize> fmt::Debug for Grid<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Grid({:#b})", self)
+    }
+}
+
+/**
+ * The number of words needed to store a grid of the given number of cells.
+ * Rounded up to a power of two, so only a few widths are ever monomorphised.
+ */
+pub const fn words_for(cells: u32) -> usize {
+    let words = (cells as usize).div_ceil(64);
+    if words <= 1 {
+        1
+    } else {
+        words.next_power_of_two()
+    }
+}
+
+/**
+ * Run $body with $W bound to a const usize wide enough for $cells cells.
+ * Bridges the runtime grid side to the const generic width of Grid.
+ */
+#[macro_export]
+macro_rules! with_grid_words {
+    ($cells:expr, $W:ident => $body:expr) => {
+        match $crate::grid::words_for($cells) {
+            1 => {
+                const $W: usize = 1;
+                $body
+            }
+            2 => {
+                const $W: usize = 2;
+                $body
+            }
+            4 => {
+                const $W: usize = 4;
+                $body
+            }
+            8 => {
+                const $W: usize = 8;
+                $body
+            }
+            16 => {
+                const $W: usize = 16;
+                $body
+            }
+            words => panic!("No grid width is dispatched for {} words.", words),
+        }
+    };
+}
@@ -0,0 +1,27 @@
+/**
+ * Author: Kier Palin
+ * The shared core of the OEIS/A227133 solvers.
+ *
+ * Owns everything that does not depend upon how a solver searches:
+ * the grid bitboard, the geometry of the board & the enumeration and validation of squares.
+ * Both naive_solution & heatmap_collapse_solution depend upon this crate,
+ * so that a fix or speedup here lands in every solver at once.
+ */
+pub mod board;
+pub mod branch_and_bound;
+pub mod certificate;
+pub mod cnf;
+pub mod grid;
+pub mod local_search;
+pub mod packing;
+pub mod pattern;
+pub mod progress;
+pub mod sat;
+pub mod squares;
+pub mod symmetry;
+pub mod upper_bound;
+pub mod violations;
+
+pub use board::{Board, Topology};
+pub use grid::Grid;
+pub use squares::{get_squares, grid_contains_squares, SquareFamily};
@@ -0,0 +1,170 @@
+/**
+ * A simulated annealing search for good grids; for the n where the exact solvers are hopeless.
+ *
+ * Each move flips a random cell. The grid may contain squares part way through the run; the energy minimised
+ * is the number of complete squares, times a penalty, less the popcount. Since the penalty is over 1,
+ * clearing a corner of a square always lowers the energy; so the grid settles on square-free grids as it cools.
+ * Worsening moves are accepted with probability e^(-change / temperature), & the temperature is cooled geometrically.
+ *
+ * A move's change in energy is known before it is made: the squares that a cell would complete (or, when painted,
+ * breaks) are counted by Violations. So only the accepted moves are ever applied.
+ *
+ * The search only finds lower bounds on a(n): its grids are square-free, but nothing proves that they are optimal.
+ * They make good incumbents for the exact solvers, though; which then only need to search for a better grid.
+ *
+ * It has its own random number generator, so that a seed always gives the same run on every platform.
+ */
+use crate::board::Board;
+use crate::grid::Grid;
+use crate::violations::Violations;
+
+/**
+ * The parameters of a run.
+ */
+#[derive(Clone, Debug)]
+pub struct Annealing {
+    pub seed: u64,
+    /**
+     * The number of moves tried.
+     */
+    pub iterations: u64,
+    pub initial_temperature: f64,
+    pub final_temperature: f64,
+    /**
+     * The energy of each complete square; relative to the 1 of each painted cell.
+     */
+    pub penalty: f64,
+}
+
+impl Default for Annealing {
+    fn default() -> Annealing {
+        Annealing {
+            seed: 0,
+            iterations: 4_000_000,
+            initial_temperature: 0.6,
+            final_temperature: 0.1,
+            penalty: 1.2,
+        }
+    }
+}
+
+/**
+ * The best square-free grid found by a run; on_improvement is passed each one that beats the best so far, as it is found.
+ */
+pub fn anneal<const W: usize>(
+    board: &Board,
+    squares: &[Grid<W>],
+    annealing: &Annealing,
+    mut on_improvement: impl FnMut(&Grid<W>),
+) -> Grid<W> {
+    let mut violations = Violations::new(board, squares);
+    let mut rng = Rng::new(annealing.seed);
+    let cooling = (annealing.final_temperature / annealing.initial_temperature)
+        .powf(1.0 / annealing.iterations.max(1) as f64);
+    let mut temperature = annealing.initial_temperature;
+
+    let mut best: Grid<W> = Grid::empty();
+    for _ in 0..annealing.iterations {
+        temperature *= cooling;
+        let cell = rng.below(board.size as u64) as u32;
+
+        // Painting lowers the energy by 1, but raises it by the penalty of each square completed:
+        let square_energy = annealing.penalty * violations.completions(cell) as f64;
+        let change = if violations.grid().test(cell) {
+            1.0 - square_energy
+        } else {
+            square_energy - 1.0
+        };
+        if change > 0.0 && rng.next_f64() >= (-change / temperature).exp() {
+            continue;
+        }
+
+        violations.flip(cell);
+        if violations.complete_qty() == 0 && violations.grid().count_ones() > best.count_ones() {
+            best = violations.grid();
+            on_improvement(&best);
+        }
+    }
+    best
+}
+
+//--------------------------
+// Random Number Generation:
+//--------------------------
+
+/**
+ * SplitMix64; small, fast & the same on every platform.
+ */
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /**
+     * Uniform in 0..bound; via the high bits of a 128 bit product, rather than a biased modulo.
+     */
+    pub fn below(&mut self, bound: u64) -> u64 {
+        ((self.next_u64() as u128 * bound as u128) >> 64) as u64
+    }
+
+    /**
+     * Uniform in [0, 1).
+     */
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::squares::{get_squares, grid_contains_squares, SquareFamily};
+
+    /**
+     * The grid a run returns, & every improvement that it passed to on_improvement.
+     */
+    fn run(board: &Board, annealing: &Annealing) -> (Grid<1>, Vec<Grid<1>>) {
+        let squares: Vec<Grid<1>> = get_squares(board);
+        let mut improvements = Vec::new();
+        let best = anneal(board, &squares, annealing, |grid| improvements.push(*grid));
+        for improvement in improvements.iter() {
+            assert!(
+                !grid_contains_squares(*improvement, &squares),
+                "{:?}",
+                board
+            );
+        }
+        (best, improvements)
+    }
+
+    #[test]
+    fn runs_are_deterministic_for_a_seed() {
+        for (board, seed) in [
+            (Board::new(6), 0),
+            (Board::rectangle(4, 7), 3),
+            (Board::new(5).with_family(SquareFamily::AllOrientations), 11),
+        ] {
+            let annealing = Annealing {
+                seed,
+                iterations: 100_000,
+                ..Annealing::default()
+            };
+            let (best, improvements) = run(&board, &annealing);
+
+            assert_eq!(improvements.last(), Some(&best), "{:?}", board);
+            assert_eq!(run(&board, &annealing), (best, improvements), "{:?}", board);
+        }
+    }
+}
@@ -0,0 +1,101 @@
+/**
+ * Greedy packings of squares that share no open corner; the one lower bound on the cells still to be cleared that
+ * the upper bound, branch-and-bound, the SAT solver & the heatmap collapse all rest on.
+ *
+ * A square with an excluded corner (one that is already cleared) needs nothing more. Every other square needs one of
+ * its open corners cleared; so if no two packed squares share an open corner, each needs a cleared cell of its own.
+ * Corners that are neither excluded nor open (those already painted) may be shared.
+ *
+ * The squares with the fewest open corners are packed first, since they block the fewest others.
+ */
+use crate::grid::Grid;
+
+/**
+ * The state that a packing is built against; so that the SAT solver's assignment can stand in for the grids.
+ */
+pub trait Packing {
+    type Square;
+
+    /**
+     * How many of the square's corners are open; None if any of them is excluded.
+     */
+    fn open_corner_qty(&self, square: &Self::Square) -> Option<u32>;
+
+    /**
+     * Whether an open corner of the square is already taken by a packed square.
+     */
+    fn overlaps(&self, square: &Self::Square) -> bool;
+
+    fn pack(&mut self, square: &Self::Square);
+}
+
+/**
+ * Pack the squares greedily, stopping once limit of them are packed.
+ * Returns the indices of the packed squares, in the order that they were packed.
+ */
+pub fn pack<P: Packing>(packing: &mut P, squares: &[P::Square], limit: usize) -> Vec<usize> {
+    let mut packed: Vec<usize> = Vec::new();
+    for open_corner_qty in 1.. {
+        let mut larger_remain = false;
+        for (index, square) in squares.iter().enumerate() {
+            match packing.open_corner_qty(square) {
+                Some(qty) if qty == open_corner_qty && !packing.overlaps(square) => {
+                    packing.pack(square);
+                    packed.push(index);
+                    if packed.len() >= limit {
+                        return packed;
+                    }
+                }
+                Some(qty) if qty > open_corner_qty => larger_remain = true,
+                _ => {}
+            }
+        }
+        if !larger_remain {
+            break;
+        }
+    }
+    packed
+}
+
+/**
+ * A packing of grids; the corners in used are those taken by the packed squares.
+ */
+struct GridPacking<const W: usize> {
+    excluded: Grid<W>,
+    open: Grid<W>,
+    used: Grid<W>,
+}
+
+impl<const W: usize> Packing for GridPacking<W> {
+    type Square = Grid<W>;
+
+    fn open_corner_qty(&self, square: &Grid<W>) -> Option<u32> {
+        (*square & self.excluded)
+            .is_empty()
+            .then(|| (*square & self.open).count_ones())
+    }
+
+    fn overlaps(&self, square: &Grid<W>) -> bool {
+        !(*square & self.open & self.used).is_empty()
+    }
+
+    fn pack(&mut self, square: &Grid<W>) {
+        self.used |= *square & self.open;
+    }
+}
+
+/**
+ * The number of squares in a greedy packing; at least that many more cells must be cleared.
+ */
+pub fn packing_bound<const W: usize>(
+    squares: &[Grid<W>],
+    excluded: &Grid<W>,
+    open: &Grid<W>,
+) -> u32 {
+    let mut packing = GridPacking {
+        excluded: *excluded,
+        open: *open,
+        used: Grid::empty(),
+    };
+    pack(&mut packing, squares, usize::MAX).len() as u32
+}
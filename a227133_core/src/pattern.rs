@@ -0,0 +1,287 @@
+/**
+ * Forbidden patterns beyond squares: a set of cells, & the transformations under which every image is forbidden too.
+ *
+ * A pattern is expanded into the same list of constraints (one Grid per placement) that get_squares returns;
+ * so every solver, the upper bounds & the heatmap collapse work on it unchanged. Some examples:
+ *
+ * - All-corner rectangles (Zarankiewicz):  cells=0,0 0,1 1,0 1,1  transforms=translation stretching
+ * - Right isosceles triangles:             cells=0,0 0,1 1,0      transforms=translation scaling rotation
+ * - L-trominoes at every scale:            cells=0,0 1,0 1,1      transforms=translation scaling rotation reflection
+ * - 3-term arithmetic progressions in rows: cells=0,0 0,1 0,2      transforms=translation scaling
+ *
+ * Patterns are read from a small text spec; one key=value per line, with # for comments:
+ *
+ * cells=<row>,<column> <row>,<column> ...
+ * transforms=<transform> ...     (any of translation, scaling, stretching, rotation, reflection & tilting)
+ */
+use crate::board::Board;
+
+use std::collections::{BTreeSet, HashSet};
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Transform {
+    /**
+     * Every position on the board; otherwise the cells stay where they are given, relative to the top-left cell.
+     */
+    Translation,
+    /**
+     * Every whole multiple of the cells' offsets.
+     */
+    Scaling,
+    /**
+     * Rows & columns scaled independently; so a square stretches into every rectangle.
+     */
+    Stretching,
+    /**
+     * The quarter turns.
+     */
+    Rotation,
+    /**
+     * The mirror image, left to right.
+     */
+    Reflection,
+    /**
+     * Every rotation & scale that maps the lattice onto itself; as for the squares in any orientation.
+     */
+    Tilting,
+}
+
+impl Transform {
+    const ALL: [Transform; 6] = [
+        Transform::Translation,
+        Transform::Scaling,
+        Transform::Stretching,
+        Transform::Rotation,
+        Transform::Reflection,
+        Transform::Tilting,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Transform::Translation => "translation",
+            Transform::Scaling => "scaling",
+            Transform::Stretching => "stretching",
+            Transform::Rotation => "rotation",
+            Transform::Reflection => "reflection",
+            Transform::Tilting => "tilting",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pattern {
+    /**
+     * The (row, column) offsets of the pattern's cells.
+     */
+    pub cells: Vec<(i64, i64)>,
+    pub transforms: Vec<Transform>,
+}
+
+/**
+ * A linear map of (row, column) offsets: (a row + b column, c row + d column).
+ */
+type LinearMap = [i64; 4];
+
+const IDENTITY: LinearMap = [1, 0, 0, 1];
+
+fn compose(outer: &LinearMap, inner: &LinearMap) -> LinearMap {
+    let ([a, b, c, d], [e, f, g, h]) = (outer, inner);
+    [a * e + b * g, a * f + b * h, c * e + d * g, c * f + d * h]
+}
+
+impl Pattern {
+    pub fn parse(spec: &str) -> Result<Pattern, String> {
+        let invalid = |line: &str| format!("Invalid line in pattern: '{}'", line);
+        let (mut cells, mut transforms) = (None, Vec::new());
+
+        for line in spec.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line.split_once('=').ok_or_else(|| invalid(line))?;
+            match key.trim() {
+                "cells" => {
+                    let parsed: Vec<(i64, i64)> = value
+                        .split_whitespace()
+                        .map(|cell| {
+                            let (row, column) = cell.split_once(',')?;
+                            Some((row.parse().ok()?, column.parse().ok()?))
+                        })
+                        .collect::<Option<_>>()
+                        .ok_or_else(|| invalid(line))?;
+                    cells = Some(parsed);
+                }
+                "transforms" => {
+                    for name in value.split_whitespace() {
+                        let transform = Transform::ALL
+                            .into_iter()
+                            .find(|transform| transform.name() == name)
+                            .ok_or_else(|| format!("Unknown transform '{}' in pattern.", name))?;
+                        if !transforms.contains(&transform) {
+                            transforms.push(transform);
+                        }
+                    }
+                }
+                _ => return Err(invalid(line)),
+            }
+        }
+
+        let cells = cells.ok_or("The pattern has no 'cells' line.")?;
+        let distinct: HashSet<&(i64, i64)> = cells.iter().collect();
+        if distinct.len() != cells.len() || cells.len() < 2 {
+            return Err("A pattern needs at least 2 cells, with none repeated.".to_string());
+        }
+        Ok(Pattern { cells, transforms })
+    }
+
+    pub fn allows(&self, transform: Transform) -> bool {
+        self.transforms.contains(&transform)
+    }
+
+    /**
+     * Every placement of the pattern on the board, as its cells in ascending order; each only once.
+     *
+     * Each linear map allowed by the transforms is applied to the cells; then, given translation, the image is
+     * moved to every position at which it fits. The scales only go as far as the board is long.
+     */
+    pub fn placements(&self, board: &Board) -> Vec<Vec<u32>> {
+        let (height, width) = (board.height as i64, board.width as i64);
+        let reach = height.max(width);
+
+        let mut scales: Vec<LinearMap> = if self.allows(Transform::Stretching) {
+            (1..reach)
+                .flat_map(|rows| (1..reach).map(move |columns| [rows, 0, 0, columns]))
+                .collect()
+        } else if self.allows(Transform::Scaling) {
+            (1..reach).map(|scale| [scale, 0, 0, scale]).collect()
+        } else {
+            vec![IDENTITY]
+        };
+        if self.allows(Transform::Tilting) {
+            // The rotation & scale that takes (0, 1) onto the lattice vector (p, q):
+            let tilts: Vec<LinearMap> = (-reach..reach)
+                .flat_map(|p| (-reach..reach).map(move |q| [q, p, -p, q]))
+                .filter(|&tilt| tilt != [0, 0, 0, 0])
+                .collect();
+            scales = tilts
+                .iter()
+                .flat_map(|tilt| scales.iter().map(move |scale| compose(tilt, scale)))
+                .collect();
+        }
+        let turns: &[LinearMap] = if self.allows(Transform::Rotation) {
+            &[IDENTITY, [0, 1, -1, 0], [-1, 0, 0, -1], [0, -1, 1, 0]]
+        } else {
+            &[IDENTITY]
+        };
+        let mirrors: &[LinearMap] = if self.allows(Transform::Reflection) {
+            &[IDENTITY, [1, 0, 0, -1]]
+        } else {
+            &[IDENTITY]
+        };
+
+        // The distinct images; placed at the top-left of the board when translated, so that they are compared by shape:
+        let mut images: BTreeSet<Vec<(i64, i64)>> = BTreeSet::new();
+        for scale in scales.iter() {
+            for turn in turns.iter() {
+                for mirror in mirrors.iter() {
+                    let [a, b, c, d] = compose(scale, &compose(turn, mirror));
+                    let mut image: Vec<(i64, i64)> = self
+                        .cells
+                        .iter()
+                        .map(|&(row, column)| (a * row + b * column, c * row + d * column))
+                        .collect();
+                    if self.allows(Transform::Translation) {
+                        let top = image.iter().map(|&(row, _)| row).min().unwrap();
+                        let left = image.iter().map(|&(_, column)| column).min().unwrap();
+                        for cell in image.iter_mut() {
+                            *cell = (cell.0 - top, cell.1 - left);
+                        }
+                    }
+                    image.sort_unstable();
+                    image.dedup();
+                    if image.len() == self.cells.len() {
+                        images.insert(image);
+                    }
+                }
+            }
+        }
+
+        let mut placements: BTreeSet<Vec<u32>> = BTreeSet::new();
+        for image in images.iter() {
+            let offsets: Vec<(i64, i64)> = if self.allows(Transform::Translation) {
+                let bottom = image.iter().map(|&(row, _)| row).max().unwrap();
+                let right = image.iter().map(|&(_, column)| column).max().unwrap();
+                (0..height - bottom)
+                    .flat_map(|row| (0..width - right).map(move |column| (row, column)))
+                    .collect()
+            } else {
+                vec![(0, 0)]
+            };
+            for (top, left) in offsets {
+                let cells: Option<Vec<u32>> = image
+                    .iter()
+                    .map(|&(row, column)| {
+                        let (row, column) = (row + top, column + left);
+                        ((0..height).contains(&row) && (0..width).contains(&column))
+                            .then_some((row * width + column) as u32)
+                    })
+                    .collect();
+                if let Some(mut cells) = cells {
+                    cells.sort_unstable();
+                    placements.insert(cells);
+                }
+            }
+        }
+        placements.into_iter().collect()
+    }
+}
+
+/**
+ * The spec that the pattern was read from; so that it can be written back out, as to a checkpoint.
+ */
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let cells: Vec<String> = self
+            .cells
+            .iter()
+            .map(|(row, column)| format!("{},{}", row, column))
+            .collect();
+        let transforms: Vec<&str> = self.transforms.iter().map(Transform::name).collect();
+        write!(
+            f,
+            "cells={}\ntransforms={}",
+            cells.join(" "),
+            transforms.join(" ")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::squares::{get_squares, SquareFamily};
+
+    #[test]
+    fn squares_as_patterns_match_the_square_families() {
+        let square = |transforms: &str| {
+            Pattern::parse(&format!("cells=0,0 0,1 1,0 1,1\ntransforms={}", transforms)).unwrap()
+        };
+        for length in 1..=7 {
+            let board = Board::new(length);
+            for (pattern, family) in [
+                (square("translation scaling"), SquareFamily::AxisParallel),
+                (square("translation tilting"), SquareFamily::AllOrientations),
+            ] {
+                let squares: BTreeSet<Vec<u32>> =
+                    get_squares::<1>(&board.clone().with_family(family))
+                        .iter()
+                        .map(|square| square.ones().collect())
+                        .collect();
+                let placements: BTreeSet<Vec<u32>> =
+                    pattern.placements(&board).into_iter().collect();
+                assert_eq!(placements, squares, "n = {}", length);
+            }
+        }
+    }
+}
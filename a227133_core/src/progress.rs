@@ -0,0 +1,188 @@
+/**
+ * Progress reporting for the level-by-level searches.
+ *
+ * A search emits an Event as it starts each level (popcount), periodically while it checks the level,
+ * & once it has finished the level. Observers subscribe to the events via a Reporter;
+ * the StatusLine observer renders them as a single, live line on stderr.
+ */
+use std::io::Write;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/**
+ * How far a search has got through its current level.
+ */
+#[derive(Clone, Debug, Default)]
+pub struct Progress {
+    pub popcount: u32,
+    /**
+     * The candidate grids checked so far in this level.
+     */
+    pub checked: u128,
+    /**
+     * The candidate grids in this level; when it is known in advance.
+     */
+    pub total: Option<u128>,
+    /**
+     * The number of states in the frontier; for the breadth-first searches.
+     */
+    pub frontier_size: Option<usize>,
+    /**
+     * The time spent in this level; filled in by the Reporter.
+     */
+    pub elapsed: Duration,
+}
+
+impl Progress {
+    /**
+     * Candidates checked per second.
+     */
+    pub fn rate(&self) -> f64 {
+        self.checked as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+
+    /**
+     * The estimated time left in this level, at the current rate.
+     */
+    pub fn estimated_remaining(&self) -> Option<Duration> {
+        let total = self.total?;
+        if self.checked == 0 {
+            return None;
+        }
+        let remaining = total.saturating_sub(self.checked) as f64 / self.rate();
+        Some(Duration::from_secs_f64(remaining.min(1e15)))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Event {
+    LevelStarted(Progress),
+    Checking(Progress),
+    LevelFinished(Progress),
+}
+
+pub trait Observer: Sync {
+    fn observe(&self, event: &Event);
+}
+
+//-----------
+// Reporting:
+//-----------
+
+/**
+ * How often the solvers' status lines are redrawn; the interval that both binaries give their Reporter.
+ */
+pub const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/**
+ * Sends the events of a search to its observers; throttling the periodic Checking events to one per interval.
+ */
+pub struct Reporter<'a> {
+    observers: Vec<&'a dyn Observer>,
+    interval: Duration,
+    level_started: Mutex<Instant>,
+    last_report: Mutex<Instant>,
+}
+
+impl<'a> Reporter<'a> {
+    pub fn new(observers: Vec<&'a dyn Observer>, interval: Duration) -> Reporter<'a> {
+        Reporter {
+            observers,
+            interval,
+            level_started: Mutex::new(Instant::now()),
+            last_report: Mutex::new(Instant::now()),
+        }
+    }
+
+    /**
+     * A reporter with no observers; every event is dropped.
+     */
+    pub fn silent() -> Reporter<'a> {
+        Reporter::new(Vec::new(), Duration::MAX)
+    }
+
+    pub fn start_level(&self, progress: Progress) {
+        *self.level_started.lock().unwrap() = Instant::now();
+        *self.last_report.lock().unwrap() = Instant::now();
+        self.send(Event::LevelStarted, progress);
+    }
+
+    pub fn finish_level(&self, progress: Progress) {
+        self.send(Event::LevelFinished, progress);
+    }
+
+    /**
+     * Report the progress, if the interval has passed since the last report.
+     * Never blocks a worker: if another is already reporting, this one carries on searching.
+     * The progress is only calculated when it is reported.
+     */
+    pub fn maybe_report(&self, progress: impl FnOnce() -> Progress) {
+        if self.observers.is_empty() {
+            return;
+        }
+        if let Ok(mut last_report) = self.last_report.try_lock() {
+            if last_report.elapsed() >= self.interval {
+                self.send(Event::Checking, progress());
+                *last_report = Instant::now();
+            }
+        }
+    }
+
+    fn send(&self, event: fn(Progress) -> Event, mut progress: Progress) {
+        if self.observers.is_empty() {
+            return;
+        }
+        progress.elapsed = self.level_started.lock().unwrap().elapsed();
+        let event = event(progress);
+        for observer in self.observers.iter() {
+            observer.observe(&event);
+        }
+    }
+}
+
+//-------------
+// Status Line:
+//-------------
+
+/**
+ * Renders the events as one line on stderr, that is overwritten in place; & cleared once each level finishes.
+ */
+#[derive(Default)]
+pub struct StatusLine {
+    width: Mutex<usize>,
+}
+
+impl Observer for StatusLine {
+    fn observe(&self, event: &Event) {
+        let mut width = self.width.lock().unwrap();
+        let line = match event {
+            Event::LevelStarted(progress) | Event::Checking(progress) => status(progress),
+            Event::LevelFinished(_) => String::new(),
+        };
+
+        // Pad over whatever is left of the previous line:
+        let mut stderr = std::io::stderr().lock();
+        let _ = write!(stderr, "\r{:<width$}\r{}", "", line, width = *width);
+        let _ = stderr.flush();
+        *width = line.chars().count();
+    }
+}
+
+fn status(progress: &Progress) -> String {
+    let mut line = format!("popcount {}: ", progress.popcount);
+    if let Some(frontier_size) = progress.frontier_size {
+        line += &format!("frontier of {}, ", frontier_size);
+    }
+    line += &match progress.total {
+        Some(total) => format!("{}/{} checked", progress.checked, total),
+        None => format!("{} checked", progress.checked),
+    };
+    if progress.checked > 0 {
+        line += &format!(" ({:.0}/s", progress.rate());
+        if let Some(remaining) = progress.estimated_remaining() {
+            line += &format!(", ~{:.0?} left", remaining);
+        }
+        line += ")";
+    }
+    line
+}
@@ -0,0 +1,896 @@
+/**
+ * A small CDCL (conflict-driven clause learning) SAT solver, specialised to the square-free problem.
+ *
+ * Variable n is true when cell n is painted. The constraints are:
+ *
+ * - The square clauses: one (-a -b -c -d) clause per square from get_squares.
+ * - "At least k painted cells"; handled natively, rather than encoded as clauses.
+ *   Once as many cells are cleared as may be, every undecided cell is forced to be painted;
+ *   & clearing any more is a conflict.
+ *
+ * The cardinality constraint is strengthened by the bound of the branch-and-bound solver:
+ * squares with no cleared corner & no undecided corner in common each still need a cell cleared.
+ * So a packing of more such squares than cells that may still be cleared is a conflict;
+ * & with exactly as many, every undecided cell outside of the packing is forced to be painted.
+ *
+ * Clauses are propagated via two watched literals, conflicts are analysed to their first unique
+ * implication point, & the learnt clause is added before backjumping. Decisions follow VSIDS activity,
+ * with phase saving & Luby restarts.
+ *
+ * Unlike the other solvers this one can also prove that no grid with k cells exists;
+ * so a(n) is settled by a model with a(n) cells & a proof of unsatisfiability at a(n) + 1.
+ * When recorded, the proof lists every learnt clause, & every reason of the cardinality constraint
+ * that a learnt clause was resolved from; in the format checked by certificate.
+ */
+use crate::board::Board;
+use crate::certificate::{Certificate, Lemma};
+use crate::grid::Grid;
+use crate::packing::{pack, Packing};
+use crate::squares::{get_squares, SquareFamily};
+
+pub enum SatResult<const W: usize> {
+    Satisfiable(Grid<W>),
+    Unsatisfiable,
+}
+
+/**
+ * Is there a grid with at least k painted cells & no square?
+ */
+pub fn solve_popcount<const W: usize>(board: &Board, squares: &[Grid<W>], k: u32) -> SatResult<W> {
+    SatSolver::new(board, squares, k).solve()
+}
+
+/**
+ * Solve a(..) for the board.
+ * Each model found raises k to one more than its popcount, until a k is proven unsatisfiable.
+ */
+pub fn solve<const W: usize>(board: &Board) -> Grid<W> {
+    solve_from(board, Grid::empty())
+}
+
+/**
+ * Like solve, but warm-started: k starts from one more than the popcount of the (square-free) incumbent.
+ */
+pub fn solve_from<const W: usize>(board: &Board, incumbent: Grid<W>) -> Grid<W> {
+    solve_recording_proof(board, incumbent, false).0
+}
+
+/**
+ * Like solve, but also returns the certificate: the solution, & the proof that a(n) + 1 is unsatisfiable.
+ * Certificates only describe plane boards of the square families; any other board is an error.
+ */
+pub fn solve_with_certificate<const W: usize>(
+    board: &Board,
+) -> Result<(Grid<W>, Certificate), String> {
+    solve_with_certificate_from(board, Grid::empty())
+}
+
+/**
+ * Like solve_with_certificate, but warm-started as solve_from is.
+ */
+pub fn solve_with_certificate_from<const W: usize>(
+    board: &Board,
+    incumbent: Grid<W>,
+) -> Result<(Grid<W>, Certificate), String> {
+    if board.is_solid() || matches!(board.family, SquareFamily::Pattern(_) | SquareFamily::Cubes) {
+        return Err(
+            "Certificates only cover the squares of plane boards, not patterns, cubes or solid boards."
+                .to_string(),
+        );
+    }
+    let (solution, lemmas) = solve_recording_proof::<W>(board, incumbent, true);
+    let certificate = Certificate {
+        height: board.height,
+        width: board.width,
+        family: board.family.clone(),
+        topology: board.topology,
+        k: solution.count_ones() + 1,
+        solution: solution.ones().collect(),
+        lemmas,
+    };
+    Ok((solution, certificate))
+}
+
+/**
+ * Returns the best model, & the proof of the final (unsatisfiable) k when recording.
+ */
+fn solve_recording_proof<const W: usize>(
+    board: &Board,
+    incumbent: Grid<W>,
+    record: bool,
+) -> (Grid<W>, Vec<Lemma>) {
+    let squares: Vec<Grid<W>> = get_squares(board);
+
+    let mut best: Grid<W> = incumbent;
+    loop {
+        let mut solver = SatSolver::new(board, &squares, best.count_ones() + 1);
+        if record {
+            solver.record_proof();
+        }
+        match solver.solve() {
+            SatResult::Satisfiable(model) => best = model,
+            SatResult::Unsatisfiable => return (best, solver.take_proof()),
+        }
+    }
+}
+
+//-------------------
+// Literals & Reasons:
+//-------------------
+
+/**
+ * A literal is 2 * variable, plus 1 when the variable is negated.
+ */
+type Literal = u32;
+
+const fn positive(variable: usize) -> Literal {
+    (variable as u32) << 1
+}
+
+const fn negative(variable: usize) -> Literal {
+    ((variable as u32) << 1) | 1
+}
+
+const fn variable_of(literal: Literal) -> usize {
+    (literal >> 1) as usize
+}
+
+const fn negate(literal: Literal) -> Literal {
+    literal ^ 1
+}
+
+/**
+ * Why a variable was assigned.
+ */
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Reason {
+    Decision,
+    Clause(usize),
+    /**
+     * Forced painted by the cardinality constraint; by the cells that were cleared before it.
+     */
+    Cardinality,
+    /**
+     * Forced painted by a packing of squares; the index of the rest of its reason in explanations.
+     */
+    Packing(usize),
+}
+
+enum Conflict {
+    Clause(usize),
+    Cardinality,
+    Packing(Explanation),
+}
+
+/**
+ * Every cleared cell, plus the painted corners of the packed squares (the indices of which are kept for proofs).
+ */
+struct Explanation {
+    literals: Vec<Literal>,
+    squares: Vec<usize>,
+}
+
+/**
+ * A packing against the assignment: the cleared cells are excluded, & the undecided ones are open.
+ */
+struct AssignmentPacking<'a> {
+    values: &'a [i8],
+    packed_cells: Vec<bool>,
+}
+
+impl Packing for AssignmentPacking<'_> {
+    type Square = Vec<usize>;
+
+    fn open_corner_qty(&self, corners: &Vec<usize>) -> Option<u32> {
+        if corners.iter().any(|&corner| self.values[corner] == FALSE) {
+            return None;
+        }
+        Some(
+            corners
+                .iter()
+                .filter(|&&corner| self.values[corner] == UNASSIGNED)
+                .count() as u32,
+        )
+    }
+
+    fn overlaps(&self, corners: &Vec<usize>) -> bool {
+        corners
+            .iter()
+            .any(|&corner| self.values[corner] == UNASSIGNED && self.packed_cells[corner])
+    }
+
+    fn pack(&mut self, corners: &Vec<usize>) {
+        for &corner in corners.iter() {
+            self.packed_cells[corner] |= self.values[corner] == UNASSIGNED;
+        }
+    }
+}
+
+//------------
+// The Solver:
+//------------
+
+const UNASSIGNED: i8 = 0;
+const TRUE: i8 = 1;
+const FALSE: i8 = -1;
+
+/**
+ * The number of conflicts in the first run before a restart; scaled by the Luby sequence.
+ */
+const RESTART_BASE: u64 = 100;
+const ACTIVITY_DECAY: f64 = 0.95;
+
+pub struct SatSolver {
+    variable_qty: usize,
+    /**
+     * At most this many cells may be cleared; the number of cells less k.
+     */
+    max_cleared: usize,
+    clauses: Vec<Vec<Literal>>,
+    squares: Vec<Vec<usize>>,
+    /**
+     * watches[l] are the clauses that watch literal l; they are visited when l becomes false.
+     */
+    watches: Vec<Vec<usize>>,
+
+    values: Vec<i8>,
+    levels: Vec<usize>,
+    reasons: Vec<Reason>,
+    trail_positions: Vec<usize>,
+    trail: Vec<Literal>,
+    /**
+     * The position in the trail at which each decision level starts.
+     */
+    level_starts: Vec<usize>,
+    /**
+     * The reasons of packing propagations, less the painted cell itself;
+     * & the position in explanations at which each decision level starts.
+     */
+    explanations: Vec<Explanation>,
+    explanation_starts: Vec<usize>,
+    propagated: usize,
+    cleared_qty: usize,
+
+    activity: Vec<f64>,
+    activity_increment: f64,
+    saved_phases: Vec<bool>,
+    /**
+     * A k larger than the grid cannot be met at all.
+     */
+    trivially_unsatisfiable: bool,
+
+    proof: Option<Vec<Lemma>>,
+    /**
+     * The level 0 assignments whose reasons are already in the proof.
+     */
+    justified: Vec<bool>,
+}
+
+impl SatSolver {
+    pub fn new<const W: usize>(board: &Board, squares: &[Grid<W>], k: u32) -> SatSolver {
+        let variable_qty = board.size as usize;
+        let mut solver = SatSolver {
+            variable_qty,
+            max_cleared: variable_qty.saturating_sub(k as usize),
+            clauses: Vec::new(),
+            squares: Vec::new(),
+            watches: vec![Vec::new(); 2 * variable_qty],
+            values: vec![UNASSIGNED; variable_qty],
+            levels: vec![0; variable_qty],
+            reasons: vec![Reason::Decision; variable_qty],
+            trail_positions: vec![0; variable_qty],
+            trail: Vec::new(),
+            level_starts: Vec::new(),
+            explanations: Vec::new(),
+            explanation_starts: Vec::new(),
+            propagated: 0,
+            cleared_qty: 0,
+            activity: vec![0.0; variable_qty],
+            activity_increment: 1.0,
+            // Painting is the goal, so cells start painted:
+            saved_phases: vec![true; variable_qty],
+            trivially_unsatisfiable: k as usize > variable_qty,
+            proof: None,
+            justified: vec![false; variable_qty],
+        };
+
+        for square in squares.iter() {
+            let clause: Vec<Literal> = square.ones().map(|cell| negative(cell as usize)).collect();
+            // Cells in many squares are the most constrained, so they are decided first:
+            for &literal in clause.iter() {
+                solver.activity[variable_of(literal)] += 1.0;
+            }
+            solver
+                .squares
+                .push(clause.iter().map(|&literal| variable_of(literal)).collect());
+            solver.add_clause(clause);
+        }
+        solver
+    }
+
+    /**
+     * Record the proof of unsatisfiability, should solve find the formula unsatisfiable.
+     */
+    pub fn record_proof(&mut self) {
+        self.proof = Some(Vec::new());
+    }
+
+    pub fn take_proof(&mut self) -> Vec<Lemma> {
+        self.proof.take().unwrap_or_default()
+    }
+
+    pub fn solve<const W: usize>(&mut self) -> SatResult<W> {
+        if self.trivially_unsatisfiable {
+            self.log_packing(Vec::new(), &[]);
+            return SatResult::Unsatisfiable;
+        }
+
+        let mut conflicts_until_restart = RESTART_BASE;
+        let mut restart_qty: u64 = 0;
+
+        loop {
+            match self.propagate() {
+                Some(conflict) => {
+                    // A packing may be found late; after the levels that it depends on:
+                    let literals = self.conflict_literals(conflict);
+                    let conflict_level = literals
+                        .iter()
+                        .map(|&literal| self.levels[variable_of(literal)])
+                        .max()
+                        .unwrap_or(0);
+                    if conflict_level == 0 {
+                        for &literal in literals.iter() {
+                            self.justify(variable_of(literal));
+                        }
+                        self.log_rup(&[]);
+                        return SatResult::Unsatisfiable;
+                    }
+                    self.backtrack(conflict_level);
+
+                    let (learnt, backjump_level) = self.analyse(literals);
+                    self.backtrack(backjump_level);
+                    self.learn(learnt);
+
+                    self.activity_increment /= ACTIVITY_DECAY;
+                    // Conflicts may follow one another before the restart is reached:
+                    conflicts_until_restart = conflicts_until_restart.saturating_sub(1);
+                }
+                None if conflicts_until_restart == 0 => {
+                    restart_qty += 1;
+                    conflicts_until_restart = RESTART_BASE * luby(restart_qty);
+                    self.backtrack(0);
+                }
+                None => match self.pick_branching_variable() {
+                    Some(variable) => {
+                        self.level_starts.push(self.trail.len());
+                        self.explanation_starts.push(self.explanations.len());
+                        let literal = if self.saved_phases[variable] {
+                            positive(variable)
+                        } else {
+                            negative(variable)
+                        };
+                        self.assign(literal, Reason::Decision);
+                    }
+                    None => return SatResult::Satisfiable(self.model()),
+                },
+            }
+        }
+    }
+
+    fn model<const W: usize>(&self) -> Grid<W> {
+        let mut grid = Grid::empty();
+        for variable in 0..self.variable_qty {
+            if self.values[variable] == TRUE {
+                grid.set(variable as u32);
+            }
+        }
+        grid
+    }
+
+    //-------------
+    // Assignments:
+    //-------------
+
+    fn decision_level(&self) -> usize {
+        self.level_starts.len()
+    }
+
+    fn value(&self, literal: Literal) -> i8 {
+        let value = self.values[variable_of(literal)];
+        if literal & 1 == 1 {
+            -value
+        } else {
+            value
+        }
+    }
+
+    fn assign(&mut self, literal: Literal, reason: Reason) {
+        let variable = variable_of(literal);
+        self.values[variable] = if literal & 1 == 1 { FALSE } else { TRUE };
+        self.levels[variable] = self.decision_level();
+        self.reasons[variable] = reason;
+        self.trail_positions[variable] = self.trail.len();
+        self.trail.push(literal);
+        if literal & 1 == 1 {
+            self.cleared_qty += 1;
+        }
+    }
+
+    fn backtrack(&mut self, level: usize) {
+        if self.decision_level() <= level {
+            return;
+        }
+        let level_start = self.level_starts[level];
+        for &literal in self.trail[level_start..].iter() {
+            let variable = variable_of(literal);
+            self.saved_phases[variable] = self.values[variable] == TRUE;
+            self.values[variable] = UNASSIGNED;
+            if literal & 1 == 1 {
+                self.cleared_qty -= 1;
+            }
+        }
+        self.trail.truncate(level_start);
+        self.level_starts.truncate(level);
+        self.explanations.truncate(self.explanation_starts[level]);
+        self.explanation_starts.truncate(level);
+        self.propagated = self.propagated.min(level_start);
+    }
+
+    fn pick_branching_variable(&self) -> Option<usize> {
+        (0..self.variable_qty)
+            .filter(|&variable| self.values[variable] == UNASSIGNED)
+            .max_by(|&a, &b| self.activity[a].total_cmp(&self.activity[b]))
+    }
+
+    //-------------
+    // Propagation:
+    //-------------
+
+    fn add_clause(&mut self, clause: Vec<Literal>) -> usize {
+        let index = self.clauses.len();
+        self.watches[clause[0] as usize].push(index);
+        if clause.len() > 1 {
+            self.watches[clause[1] as usize].push(index);
+        }
+        self.clauses.push(clause);
+        index
+    }
+
+    /**
+     * Propagate every assignment on the trail that has not yet been propagated.
+     */
+    fn propagate(&mut self) -> Option<Conflict> {
+        loop {
+            while self.propagated < self.trail.len() {
+                let literal = self.trail[self.propagated];
+                self.propagated += 1;
+
+                if literal & 1 == 1 {
+                    if let Some(conflict) = self.propagate_cardinality() {
+                        return Some(conflict);
+                    }
+                }
+                if let Some(conflict) = self.propagate_clauses(negate(literal)) {
+                    return Some(conflict);
+                }
+            }
+
+            // The packing is only sought once the cheaper propagators have nothing left:
+            if let Some(conflict) = self.propagate_packing() {
+                return Some(conflict);
+            }
+            if self.propagated == self.trail.len() {
+                return None;
+            }
+        }
+    }
+
+    /**
+     * Once as many cells are cleared as may be, every undecided cell must be painted.
+     */
+    fn propagate_cardinality(&mut self) -> Option<Conflict> {
+        if self.cleared_qty > self.max_cleared {
+            return Some(Conflict::Cardinality);
+        }
+        if self.cleared_qty == self.max_cleared {
+            for variable in 0..self.variable_qty {
+                if self.values[variable] == UNASSIGNED {
+                    self.assign(positive(variable), Reason::Cardinality);
+                }
+            }
+        }
+        None
+    }
+
+    /**
+     * Compare a packing of the current assignment's squares (see a227133_core::packing) with the slack,
+     * the number of cells that may still be cleared. The packing stops as soon as it exceeds the slack.
+     *
+     * The reason is every cleared cell, plus the painted corners of the packed squares:
+     * while they stay as they are, each packed square needs one of its own undecided corners cleared.
+     */
+    fn propagate_packing(&mut self) -> Option<Conflict> {
+        let slack = self.max_cleared - self.cleared_qty;
+        let mut packing = AssignmentPacking {
+            values: &self.values,
+            packed_cells: vec![false; self.variable_qty],
+        };
+        let packed = pack(&mut packing, &self.squares, slack + 1);
+        let packed_cells = packing.packed_cells;
+        if packed.len() < slack {
+            return None;
+        }
+
+        let mut reason: Vec<Literal> = self
+            .trail
+            .iter()
+            .filter(|&&literal| literal & 1 == 1)
+            .map(|&literal| negate(literal))
+            .collect();
+        for &index in packed.iter() {
+            reason.extend(
+                self.squares[index]
+                    .iter()
+                    .filter(|&&corner| self.values[corner] == TRUE)
+                    .map(|&corner| negative(corner)),
+            );
+        }
+        // Packed squares may share painted corners:
+        reason.sort_unstable();
+        reason.dedup();
+        let explanation = Explanation {
+            literals: reason,
+            squares: packed,
+        };
+        if explanation.squares.len() > slack {
+            return Some(Conflict::Packing(explanation));
+        }
+
+        let explanation_index = self.explanations.len();
+        let mut forced = false;
+        for (variable, &is_packed) in packed_cells.iter().enumerate() {
+            if self.values[variable] == UNASSIGNED && !is_packed {
+                self.assign(positive(variable), Reason::Packing(explanation_index));
+                forced = true;
+            }
+        }
+        if forced {
+            self.explanations.push(explanation);
+        }
+        None
+    }
+
+    /**
+     * Visit the clauses watching a literal that has just become false;
+     * each either finds another literal to watch, becomes unit, or is a conflict.
+     */
+    fn propagate_clauses(&mut self, false_literal: Literal) -> Option<Conflict> {
+        let mut watching = std::mem::take(&mut self.watches[false_literal as usize]);
+        let mut conflict = None;
+        let mut kept = 0;
+
+        for position in 0..watching.len() {
+            let index = watching[position];
+            if conflict.is_some() {
+                watching[kept] = index;
+                kept += 1;
+                continue;
+            }
+
+            // Keep the false literal in the second slot:
+            let clause = &mut self.clauses[index];
+            if clause[0] == false_literal {
+                clause.swap(0, 1);
+            }
+
+            let first = clause[0];
+            if self.value(first) == TRUE {
+                watching[kept] = index;
+                kept += 1;
+                continue;
+            }
+
+            let clause = &self.clauses[index];
+            let replacement =
+                (2..clause.len()).find(|&position| self.value(clause[position]) != FALSE);
+            match replacement {
+                Some(replacement) => {
+                    let clause = &mut self.clauses[index];
+                    clause.swap(1, replacement);
+                    let new_watch = clause[1];
+                    self.watches[new_watch as usize].push(index);
+                }
+                None => {
+                    watching[kept] = index;
+                    kept += 1;
+                    if self.value(first) == FALSE {
+                        conflict = Some(Conflict::Clause(index));
+                    } else {
+                        self.assign(first, Reason::Clause(index));
+                    }
+                }
+            }
+        }
+
+        watching.truncate(kept);
+        self.watches[false_literal as usize] = watching;
+        conflict
+    }
+
+    //-------------------
+    // Conflict Analysis:
+    //-------------------
+
+    /**
+     * The literals of the clause that implied the variable (including its own literal, which is true);
+     * for the cardinality constraint, that is the cell itself or any of the cells cleared before it.
+     * Reasons that are not clauses are added to the proof, since the resolution needs them.
+     */
+    fn reason_literals(&mut self, variable: usize) -> Vec<Literal> {
+        match self.reasons[variable] {
+            Reason::Clause(index) => self.clauses[index].clone(),
+            Reason::Cardinality => {
+                let mut literals = vec![positive(variable)];
+                literals.extend(
+                    self.trail[..self.trail_positions[variable]]
+                        .iter()
+                        .filter(|&&literal| literal & 1 == 1)
+                        .map(|&literal| negate(literal)),
+                );
+                self.log_packing(literals.clone(), &[]);
+                literals
+            }
+            Reason::Packing(explanation) => {
+                let mut literals = vec![positive(variable)];
+                literals.extend(self.explanations[explanation].literals.iter());
+                let squares = self.explanations[explanation].squares.clone();
+                self.log_packing(literals.clone(), &squares);
+                literals
+            }
+            Reason::Decision => unreachable!("Decisions have no reason."),
+        }
+    }
+
+    fn conflict_literals(&mut self, conflict: Conflict) -> Vec<Literal> {
+        match conflict {
+            Conflict::Clause(index) => self.clauses[index].clone(),
+            Conflict::Packing(explanation) => {
+                self.log_packing(explanation.literals.clone(), &explanation.squares);
+                explanation.literals
+            }
+            // Too many cells are cleared; at least one of them must be painted:
+            Conflict::Cardinality => {
+                let literals: Vec<Literal> = self
+                    .trail
+                    .iter()
+                    .filter(|&&literal| literal & 1 == 1)
+                    .map(|&literal| negate(literal))
+                    .collect();
+                self.log_packing(literals.clone(), &[]);
+                literals
+            }
+        }
+    }
+
+    /**
+     * Resolve the conflict back to its first unique implication point.
+     * Returns the learnt clause (its first literal is the one that becomes unit) & the level to backjump to.
+     */
+    fn analyse(&mut self, mut literals: Vec<Literal>) -> (Vec<Literal>, usize) {
+        let mut seen = vec![false; self.variable_qty];
+        let mut learnt: Vec<Literal> = vec![0];
+        let mut current_level_qty = 0;
+        let mut trail_index = self.trail.len();
+
+        let first_uip = loop {
+            for &literal in literals.iter() {
+                let variable = variable_of(literal);
+                if seen[variable] {
+                    continue;
+                }
+                if self.levels[variable] == 0 {
+                    // Dropped from the learnt clause; but the proof must still be able to derive it:
+                    self.justify(variable);
+                    continue;
+                }
+                seen[variable] = true;
+                self.bump_activity(variable);
+                if self.levels[variable] == self.decision_level() {
+                    current_level_qty += 1;
+                } else {
+                    learnt.push(literal);
+                }
+            }
+
+            // The most recently assigned literal of the current level that is in the clause:
+            loop {
+                trail_index -= 1;
+                if seen[variable_of(self.trail[trail_index])] {
+                    break;
+                }
+            }
+            let implied = self.trail[trail_index];
+            let variable = variable_of(implied);
+            seen[variable] = false;
+            current_level_qty -= 1;
+            if current_level_qty == 0 {
+                break implied;
+            }
+            literals = self.reason_literals(variable);
+            literals.retain(|&literal| literal != implied);
+        };
+        learnt[0] = negate(first_uip);
+
+        // Watch the literal of the highest remaining level second, so it is the first to be unassigned:
+        let mut backjump_level = 0;
+        if learnt.len() > 1 {
+            let highest = (1..learnt.len())
+                .max_by_key(|&position| self.levels[variable_of(learnt[position])])
+                .unwrap();
+            learnt.swap(1, highest);
+            backjump_level = self.levels[variable_of(learnt[1])];
+        }
+        (learnt, backjump_level)
+    }
+
+    fn learn(&mut self, learnt: Vec<Literal>) {
+        self.log_rup(&learnt);
+        let asserting = learnt[0];
+        if learnt.len() == 1 {
+            self.assign(asserting, Reason::Decision);
+            // Implied at level 0; its reason is never needed, since level 0 is never analysed.
+            return;
+        }
+        let index = self.add_clause(learnt);
+        self.assign(asserting, Reason::Clause(index));
+    }
+
+    //--------
+    // Proofs:
+    //--------
+
+    fn dimacs(literal: Literal) -> i32 {
+        let variable = variable_of(literal) as i32 + 1;
+        if literal & 1 == 1 {
+            -variable
+        } else {
+            variable
+        }
+    }
+
+    fn log_rup(&mut self, clause: &[Literal]) {
+        if let Some(proof) = self.proof.as_mut() {
+            proof.push(Lemma::Rup(
+                clause.iter().map(|&l| Self::dimacs(l)).collect(),
+            ));
+        }
+    }
+
+    fn log_packing(&mut self, clause: Vec<Literal>, squares: &[usize]) {
+        if let Some(proof) = self.proof.as_mut() {
+            proof.push(Lemma::Packing {
+                clause: clause.into_iter().map(Self::dimacs).collect(),
+                squares: squares
+                    .iter()
+                    .map(|&index| {
+                        self.squares[index]
+                            .iter()
+                            .map(|&corner| corner as u32)
+                            .collect()
+                    })
+                    .collect(),
+            });
+        }
+    }
+
+    /**
+     * Add the reasons of a level 0 assignment to the proof, & of the assignments that those depend on;
+     * so that unit propagation in the proof checker re-derives it.
+     */
+    fn justify(&mut self, variable: usize) {
+        if self.proof.is_none() {
+            return;
+        }
+        let mut unjustified = vec![variable];
+        while let Some(variable) = unjustified.pop() {
+            if self.justified[variable] {
+                continue;
+            }
+            self.justified[variable] = true;
+            // Learnt units are already in the proof:
+            if self.reasons[variable] == Reason::Decision {
+                continue;
+            }
+            for literal in self.reason_literals(variable) {
+                unjustified.push(variable_of(literal));
+            }
+        }
+    }
+
+    fn bump_activity(&mut self, variable: usize) {
+        self.activity[variable] += self.activity_increment;
+        if self.activity[variable] > 1e100 {
+            for activity in self.activity.iter_mut() {
+                *activity *= 1e-100;
+            }
+            self.activity_increment *= 1e-100;
+        }
+    }
+}
+
+/**
+ * The Luby sequence: 1, 1, 2, 1, 1, 2, 4, 1, 1, 2, ...; i is 1-based.
+ */
+fn luby(i: u64) -> u64 {
+    let mut k = 1;
+    while (1 << k) - 1 < i {
+        k += 1;
+    }
+    if (1 << k) - 1 == i {
+        1 << (k - 1)
+    } else {
+        luby(i - (1 << (k - 1)) + 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::squares::grid_contains_squares;
+    use crate::upper_bound::KNOWN_TERMS;
+
+    /**
+     * a(1..=6); the larger terms take too long for a test.
+     */
+    const TERMS: &[u32] = KNOWN_TERMS.split_at(6).0;
+
+    #[test]
+    fn solve_matches_the_known_terms() {
+        for (length, &term) in (1..).zip(TERMS.iter()) {
+            let board = Board::new(length);
+            let solution: Grid<1> = solve(&board);
+
+            assert_eq!(solution.count_ones(), term, "n = {}", length);
+            assert!(!grid_contains_squares(solution, &get_squares(&board)));
+        }
+    }
+
+    #[test]
+    fn certificates_refuse_boards_they_cannot_describe() {
+        for board in [
+            Board::cuboid(2, 2, 2),
+            Board::new(3).with_family(SquareFamily::Cubes),
+            Board::cuboid(3, 3, 2).with_family(SquareFamily::Cubes),
+        ] {
+            assert!(solve_with_certificate::<1>(&board).is_err(), "{:?}", board);
+        }
+    }
+
+    #[test]
+    fn one_more_than_the_term_is_refuted() {
+        for (length, &term) in (1..).zip(TERMS.iter()) {
+            let board = Board::new(length);
+            let squares: Vec<Grid<1>> = get_squares(&board);
+
+            match solve_popcount(&board, &squares, term) {
+                SatResult::Satisfiable(model) => {
+                    assert!(model.count_ones() >= term);
+                    assert!(!grid_contains_squares(model, &squares));
+                }
+                SatResult::Unsatisfiable => {
+                    panic!("n = {}: {} cells is satisfiable.", length, term)
+                }
+            }
+
+            let mut solver = SatSolver::new(&board, &squares, term + 1);
+            solver.record_proof();
+            assert!(matches!(solver.solve::<1>(), SatResult::Unsatisfiable));
+            let proof = solver.take_proof();
+            let last_clause = match proof.last() {
+                Some(Lemma::Rup(clause)) | Some(Lemma::Packing { clause, .. }) => clause,
+                None => panic!("n = {}: the refutation is empty.", length),
+            };
+            assert!(last_clause.is_empty(), "n = {}", length);
+        }
+    }
+}
@@ -0,0 +1,337 @@
+/**
+ * The enumeration & validation of the squares in a grid.
+ *
+ * Indices passed to the helpers here are 1-based (as the top-left corner of a square),
+ * whereas bits in a Grid are 0-based.
+ */
+use crate::board::{Board, Topology};
+use crate::grid::Grid;
+use crate::pattern::{Pattern, Transform};
+
+use itertools::iproduct; // used by get_squares.
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::sync::Arc;
+
+/**
+ * Which squares a grid must avoid: those with sides parallel to the grid (A227133's), or squares in any orientation.
+ * Every solver reads its squares from get_squares, so each works unchanged on either family.
+ *
+ * Any other forbidden pattern may stand in for the squares; its placements are then the solvers' "squares".
+ * On a solid board, the axis-parallel squares are those in any coordinate plane; or the cubes may be forbidden instead.
+ */
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum SquareFamily {
+    #[default]
+    AxisParallel,
+    AllOrientations,
+    Pattern(Arc<Pattern>),
+    /**
+     * The 8 corners of each axis-parallel cube of a solid board.
+     */
+    Cubes,
+}
+
+impl SquareFamily {
+    pub fn parse(argument: &str) -> Result<SquareFamily, String> {
+        match argument {
+            "axis-parallel" => Ok(SquareFamily::AxisParallel),
+            "all-orientations" => Ok(SquareFamily::AllOrientations),
+            "cubes" => Ok(SquareFamily::Cubes),
+            other => Err(format!(
+                "Unknown square family '{}': expected axis-parallel, all-orientations or cubes.",
+                other
+            )),
+        }
+    }
+}
+
+/**
+ * As it is given on the command line, & in checkpoints & certificates.
+ */
+impl fmt::Display for SquareFamily {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            SquareFamily::AxisParallel => "axis-parallel",
+            SquareFamily::AllOrientations => "all-orientations",
+            SquareFamily::Pattern(_) => "pattern",
+            SquareFamily::Cubes => "cubes",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+//-----------------------------
+// Square Generation Functions:
+//-----------------------------
+
+/**
+ * All possible squares in the grid can be pre-calculated.
+ * These can then be checked against a candidate_grid via bitwise anding.
+ */
+pub fn get_squares<const W: usize>(board: &Board) -> Vec<Grid<W>> {
+    if board.is_solid() {
+        return get_solid_squares(board);
+    }
+    let squares = match &board.family {
+        SquareFamily::Cubes => Vec::new(),
+        SquareFamily::AxisParallel => iproduct!(1..=board.size, 2..=board.shorter_side())
+            .filter(|(index, scale)| valid_square(board, *index, *scale))
+            .map(|(index, scale)| construct_square(board, index, scale))
+            .collect(),
+        SquareFamily::AllOrientations => get_tilted_squares(board),
+        SquareFamily::Pattern(pattern) => pattern
+            .placements(board)
+            .iter()
+            .map(|cells| {
+                let mut square = Grid::empty();
+                for &cell in cells.iter() {
+                    square.set(cell);
+                }
+                square
+            })
+            .collect(),
+    };
+    match board.topology {
+        Topology::Plane => squares,
+        Topology::Torus => wrap_squares(board, &squares),
+    }
+}
+
+/**
+ * On a torus, each square of the plane board may be moved to any position; wrapping across the edges.
+ * The squares that wrap onto the same cells (as the squares of sides d & n - d do) are only kept once.
+ * A pattern that does not allow translation stays where it is.
+ */
+fn wrap_squares<const W: usize>(board: &Board, squares: &[Grid<W>]) -> Vec<Grid<W>> {
+    if let SquareFamily::Pattern(pattern) = &board.family {
+        if !pattern.allows(Transform::Translation) {
+            return squares.to_vec();
+        }
+    }
+
+    // Each square's corners, relative to the top-left of the square; so that squares of the same shape coincide:
+    let shapes: BTreeSet<Vec<(u32, u32)>> = squares
+        .iter()
+        .map(|square| {
+            let corners: Vec<(u32, u32)> = square
+                .ones()
+                .map(|cell| (cell / board.width, cell % board.width))
+                .collect();
+            let top = corners.iter().map(|&(row, _)| row).min().unwrap_or(0);
+            let left = corners.iter().map(|&(_, column)| column).min().unwrap_or(0);
+            let mut shape: Vec<(u32, u32)> = corners
+                .iter()
+                .map(|&(row, column)| (row - top, column - left))
+                .collect();
+            shape.sort_unstable();
+            shape
+        })
+        .collect();
+
+    let mut wrapped: BTreeSet<Grid<W>> = BTreeSet::new();
+    for shape in shapes.iter() {
+        for (row, column) in iproduct!(0..board.height, 0..board.width) {
+            let mut square = Grid::empty();
+            for &(down, across) in shape.iter() {
+                square.set(
+                    (row + down) % board.height * board.width + (column + across) % board.width,
+                );
+            }
+            wrapped.insert(square);
+        }
+    }
+    wrapped.into_iter().collect()
+}
+
+/**
+ * The squares of a solid board: the axis-parallel squares in each coordinate plane, or the cubes.
+ * Each is given by its corner nearest the origin, the axes it spans & its side; its corners are that cell,
+ * moved side - 1 cells along every subset of those axes.
+ */
+pub fn get_solid_squares<const W: usize>(board: &Board) -> Vec<Grid<W>> {
+    let lengths = [board.depth, board.height, board.width];
+    // The spanned axes as bitmasks of (layer, row, column); the squares lie in the 3 planes of 2 axes:
+    let spans: &[u32] = match board.family {
+        SquareFamily::Cubes => &[0b111],
+        _ => &[0b011, 0b101, 0b110],
+    };
+    let longest = lengths.into_iter().max().unwrap_or(1);
+
+    iproduct!(0..board.size, 1..longest, spans.iter())
+        .filter_map(|(cell, offset, &span)| {
+            let origin = board.coordinates(cell);
+            let spanned = |axis: usize| span & (0b100 >> axis) != 0;
+            if (0..3).any(|axis| spanned(axis) && origin[axis] + offset >= lengths[axis]) {
+                return None;
+            }
+            let mut square = Grid::empty();
+            for subset in (0..8).filter(|subset| subset & !span == 0) {
+                let mut corner = origin;
+                for (axis, coordinate) in corner.iter_mut().enumerate() {
+                    if subset & (0b100 >> axis) != 0 {
+                        *coordinate += offset;
+                    }
+                }
+                square.set(board.cell(corner));
+            }
+            Some(square)
+        })
+        .collect()
+}
+
+/**
+ * The squares in any orientation; each given by a corner cell & the vector (dx, dy) along the side that leaves it.
+ * Of a square's 4 sides (taken in turn), exactly one has dx >= 1 & dy >= 0; so each square is generated once.
+ * The axis-parallel squares are those with dy = 0.
+ */
+pub fn get_tilted_squares<const W: usize>(board: &Board) -> Vec<Grid<W>> {
+    let side = board.shorter_side();
+    iproduct!(0..board.size, 1..side, 0..side)
+        .filter(|(_, dx, dy)| dx + dy < side)
+        .filter_map(|(cell, dx, dy)| construct_tilted_square(board, cell, dx, dy))
+        .collect()
+}
+
+/**
+ * The square with a corner on cell (0-based) & a side along (dx, dy), turning clockwise; if it lies within the board.
+ * Its corners are cell + (dx, dy), cell + (dx - dy, dx + dy) & cell + (-dy, dx); as (column, row) offsets.
+ */
+pub fn construct_tilted_square<const W: usize>(
+    board: &Board,
+    cell: u32,
+    dx: u32,
+    dy: u32,
+) -> Option<Grid<W>> {
+    let (row, column) = (cell / board.width, cell % board.width);
+    if column < dy || column + dx >= board.width || row + dx + dy >= board.height {
+        return None;
+    }
+    let corner = |row: u32, column: u32| row * board.width + column;
+
+    let mut square = Grid::empty();
+    square.set(cell);
+    square.set(corner(row + dy, column + dx));
+    square.set(corner(row + dx + dy, column + dx - dy));
+    square.set(corner(row + dx, column - dy));
+    Some(square)
+}
+
+/**
+ * A square is a grid of entirely zeroes, except for 4 set bits,
+ * These 4 set bits are the corners of the square.
+ */
+pub fn construct_square<const W: usize>(
+    board: &Board,
+    top_left_corner_index: u32,
+    scale: u32,
+) -> Grid<W> {
+    let top_left = top_left_corner_index - 1;
+    let bottom_left = top_left + (board.width * (scale - 1));
+
+    let mut square = Grid::empty();
+    square.set(top_left);
+    square.set(top_left + scale - 1);
+    square.set(bottom_left);
+    square.set(bottom_left + scale - 1);
+    square
+}
+
+//------------------------------------
+// Square Generation Helper Functions:
+//------------------------------------
+
+pub fn valid_square(board: &Board, index: u32, scale: u32) -> bool {
+    square_within_bounds(board, index, scale) & !edge_is_on_different_row(board, index, scale)
+}
+
+pub fn get_current_row(board: &Board, index: u32) -> u32 {
+    ((index - 1) + board.width - ((index - 1) % board.width)) / board.width
+}
+
+fn edge_is_on_different_row(board: &Board, index: u32, scale: u32) -> bool {
+    (get_current_row(board, index + scale - 1) - get_current_row(board, index)) > 0
+}
+
+pub fn square_within_bounds(board: &Board, index: u32, scale: u32) -> bool {
+    (index + (scale - 1) + board.width * (scale - 1)) <= board.size
+}
+
+//------------------------
+// Grid Checking Function:
+//------------------------
+
+/**
+ * Bitwise and the grid & each square.
+ * If the result is equal to the square then the grid neccessarily contains that square.
+ */
+pub fn grid_contains_squares<const W: usize>(grid: Grid<W>, squares: &[Grid<W>]) -> bool {
+    squares.iter().any(|square| square.is_subset_of(&grid))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn tilted_squares_are_every_square_once() {
+        for length in 1..=8 {
+            let board = Board::new(length).with_family(SquareFamily::AllOrientations);
+            let squares: Vec<Grid<1>> = get_squares(&board);
+            let distinct: HashSet<Grid<1>> = squares.iter().copied().collect();
+
+            // An n×n lattice has n^2 (n^2 - 1) / 12 squares, in every orientation:
+            assert_eq!(
+                squares.len() as u32,
+                length * length * (length * length - 1) / 12
+            );
+            assert_eq!(distinct.len(), squares.len());
+            assert!(get_squares::<1>(&Board::new(length))
+                .iter()
+                .all(|square| distinct.contains(square)));
+        }
+    }
+
+    #[test]
+    fn solid_squares_are_counted_by_side() {
+        for length in 1..=6 {
+            let board = Board::cuboid(length, length, length);
+            let squares: Vec<Grid<4>> = get_squares(&board);
+            let cubes: Vec<Grid<4>> = get_squares(&board.clone().with_family(SquareFamily::Cubes));
+
+            // (n - d)^2 squares of side d in each of the 3n coordinate planes; (n - d)^3 cubes of side d:
+            let count = |power: u32| {
+                (1..length)
+                    .map(|side| (length - side).pow(power))
+                    .sum::<u32>()
+            };
+            assert_eq!(
+                squares.len() as u32,
+                3 * length * count(2),
+                "n = {}",
+                length
+            );
+            assert_eq!(cubes.len() as u32, count(3), "n = {}", length);
+            assert!(squares.iter().all(|square| square.count_ones() == 4));
+            assert!(cubes.iter().all(|cube| cube.count_ones() == 8));
+        }
+    }
+
+    #[test]
+    fn wrapped_squares_of_sides_d_and_n_minus_d_coincide() {
+        for length in 2..=8 {
+            let board = Board::new(length).with_topology(Topology::Torus);
+            let squares: Vec<Grid<1>> = get_squares(&board);
+
+            // Every side but n / 2 pairs up with another; a square of side n / 2 coincides with 3 of its translates:
+            let mut expected = length * length * ((length - 1) / 2);
+            if length % 2 == 0 {
+                expected += length * length / 4;
+            }
+            assert_eq!(squares.len() as u32, expected, "n = {}", length);
+            assert!(squares.iter().all(|square| square.count_ones() == 4));
+        }
+    }
+}
@@ -0,0 +1,290 @@
+/**
+ * The symmetries of the board: the dihedral group D4 of 4 rotations & 4 reflections.
+ * A rectangular board only keeps the rotation by 180 & the 2 reflections parallel to its sides.
+ * A torus adds every translation (wrapping across the edges), & each of those composed with the above.
+ * A solid board has those of a cuboid: its axes permuted (where they are of the same length) & reversed; 48 for a cube.
+ * Of these, only the ones that map the squares onto one another are kept; all of them do for the square families,
+ * but a pattern's placements need not be as symmetric. Those that do are a subgroup, as any such set is.
+ *
+ * A grid & its rotations/reflections either all contain a square or all do not,
+ * so only one grid of each family needs to be kept.
+ * The policy is to only keep the grids that are the smallest integer out of all of their images;
+ * this requires no communication between threads, since each grid can be tested on its own.
+ *
+ * Transforming a grid is made cheap by pre-calculating where each bit moves to (a lookup table per symmetry).
+ */
+use crate::board::{Board, Topology};
+use crate::grid::Grid;
+use crate::squares::get_squares;
+use crate::with_grid_words;
+
+use itertools::iproduct;
+
+use std::collections::HashSet;
+
+/**
+ * Maps a (row, column) to its image, given the index of the last row & of the last column.
+ */
+type CellTransform = fn(u32, u32, u32, u32) -> (u32, u32);
+
+pub struct Symmetries {
+    /**
+     * lookup_tables[t][n] is the cell that cell n is moved to by symmetry t.
+     * The identity is omitted, since every grid is trivially its own image under it.
+     */
+    lookup_tables: Vec<Vec<u32>>,
+    /**
+     * inverse_lookup_tables[t][n] is the cell that is moved to cell n by symmetry t.
+     */
+    inverse_lookup_tables: Vec<Vec<u32>>,
+    size: u32,
+}
+
+impl Symmetries {
+    pub fn new(board: &Board) -> Symmetries {
+        let (last_row, last_column) = (board.height - 1, board.width - 1);
+        // The symmetries of a rectangle come first; the rest only map a square board onto itself:
+        let d4: [CellTransform; 7] = [
+            |row, column, last_row, last_column| (last_row - row, last_column - column), // Rotate 180.
+            |row, column, _, last_column| (row, last_column - column), // Reflect left to right.
+            |row, column, last_row, _| (last_row - row, column),       // Reflect top to bottom.
+            |row, column, last, _| (column, last - row),               // Rotate 90.
+            |row, column, last, _| (last - column, row),               // Rotate 270.
+            |row, column, _, _| (column, row),                         // Reflect on the diagonal.
+            |row, column, last, _| (last - column, last - row), // Reflect on the anti-diagonal.
+        ];
+        let symmetry_qty = if board.is_square() { 7 } else { 3 };
+
+        let mut lookup_tables: Vec<Vec<u32>> = if board.is_solid() {
+            solid_lookup_tables(board)
+        } else {
+            d4[..symmetry_qty]
+                .iter()
+                .map(|transform| {
+                    (0..board.size)
+                        .map(|cell| {
+                            let (row, column) = transform(
+                                cell / board.width,
+                                cell % board.width,
+                                last_row,
+                                last_column,
+                            );
+                            row * board.width + column
+                        })
+                        .collect()
+                })
+                .collect()
+        };
+        if board.topology == Topology::Torus {
+            let identity: Vec<u32> = (0..board.size).collect();
+            let turns: Vec<Vec<u32>> = std::iter::once(identity).chain(lookup_tables).collect();
+            lookup_tables = iproduct!(0..board.height, 0..board.width, turns.iter())
+                .skip(1) // The identity, translated by nothing.
+                .map(|(down, across, turn)| {
+                    turn.iter()
+                        .map(|&cell| {
+                            let (row, column) = (cell / board.width, cell % board.width);
+                            (row + down) % board.height * board.width
+                                + (column + across) % board.width
+                        })
+                        .collect()
+                })
+                .collect();
+        }
+
+        let squares: Vec<Vec<u32>> = with_grid_words!(board.size, W => get_squares::<W>(board)
+            .iter()
+            .map(|square| square.ones().collect())
+            .collect());
+        let square_set: HashSet<&Vec<u32>> = squares.iter().collect();
+        lookup_tables.retain(|lookup_table| {
+            squares.iter().all(|corners| {
+                let mut image: Vec<u32> = corners
+                    .iter()
+                    .map(|&corner| lookup_table[corner as usize])
+                    .collect();
+                image.sort_unstable();
+                square_set.contains(&image)
+            })
+        });
+
+        let inverse_lookup_tables = lookup_tables
+            .iter()
+            .map(|lookup_table| {
+                let mut inverse_lookup_table = vec![0; board.size as usize];
+                for (cell, &image) in lookup_table.iter().enumerate() {
+                    inverse_lookup_table[image as usize] = cell as u32;
+                }
+                inverse_lookup_table
+            })
+            .collect();
+
+        Symmetries {
+            lookup_tables,
+            inverse_lookup_tables,
+            size: board.size,
+        }
+    }
+
+    /**
+     * The number of symmetries, excluding the identity.
+     */
+    pub fn len(&self) -> usize {
+        self.lookup_tables.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lookup_tables.is_empty()
+    }
+
+    /**
+     * The cell that cell is moved to by the symmetry'th symmetry.
+     */
+    pub fn transform_cell(&self, symmetry: usize, cell: u32) -> u32 {
+        self.lookup_tables[symmetry][cell as usize]
+    }
+
+    /**
+     * Move every set bit of the grid to its position under the symmetry'th symmetry.
+     */
+    pub fn transform<const W: usize>(&self, symmetry: usize, grid: &Grid<W>) -> Grid<W> {
+        let lookup_table = &self.lookup_tables[symmetry];
+        let mut image = Grid::empty();
+        for cell in grid.ones() {
+            image.set(lookup_table[cell as usize]);
+        }
+        image
+    }
+
+    /**
+     * Is the grid the smallest integer out of all of its rotations & reflections?
+     *
+     * The images are never built: bit n of an image is the bit of the grid that the inverse lookup table
+     * sends to n. So the grid & each image are compared from the most significant bit down,
+     * which almost always differs within the first few bits.
+     */
+    pub fn is_canonical<const W: usize>(&self, grid: &Grid<W>) -> bool {
+        self.inverse_lookup_tables
+            .iter()
+            .all(|inverse_lookup_table| {
+                for cell in (0..self.size).rev() {
+                    let grid_bit = grid.test(cell);
+                    let image_bit = grid.test(inverse_lookup_table[cell as usize]);
+                    if grid_bit != image_bit {
+                        return image_bit;
+                    }
+                }
+                true
+            })
+    }
+
+    /**
+     * The smallest integer out of the grid & all of its rotations & reflections.
+     */
+    pub fn canonical<const W: usize>(&self, grid: &Grid<W>) -> Grid<W> {
+        (0..self.len())
+            .map(|symmetry| self.transform(symmetry, grid))
+            .fold(*grid, |smallest, image| smallest.min(image))
+    }
+
+    /**
+     * The grid & each of its distinct images; its family of rotations & reflections, in ascending order.
+     */
+    pub fn family<const W: usize>(&self, grid: &Grid<W>) -> Vec<Grid<W>> {
+        let mut family: Vec<Grid<W>> = (0..self.len())
+            .map(|symmetry| self.transform(symmetry, grid))
+            .chain(std::iter::once(*grid))
+            .collect();
+        family.sort();
+        family.dedup();
+        family
+    }
+
+    /**
+     * The symmetries that map the grid onto itself.
+     */
+    pub fn stabiliser<const W: usize>(&self, grid: &Grid<W>) -> Vec<usize> {
+        (0..self.len())
+            .filter(|&symmetry| self.transform(symmetry, grid) == *grid)
+            .collect()
+    }
+}
+
+/**
+ * The lookup tables of a solid board: each permutation of the (layer, row, column) axes that only swaps axes
+ * of the same length, with any subset of the axes reversed.
+ */
+fn solid_lookup_tables(board: &Board) -> Vec<Vec<u32>> {
+    let lengths = [board.depth, board.height, board.width];
+    let permutations: [[usize; 3]; 6] = [
+        [0, 1, 2],
+        [0, 2, 1],
+        [1, 0, 2],
+        [1, 2, 0],
+        [2, 0, 1],
+        [2, 1, 0],
+    ];
+    iproduct!(permutations, 0..8u32)
+        .skip(1) // The identity; no axis swapped or reversed.
+        .filter(|(permutation, _)| (0..3).all(|axis| lengths[permutation[axis]] == lengths[axis]))
+        .map(|(permutation, reversals)| {
+            (0..board.size)
+                .map(|cell| {
+                    let coordinates = board.coordinates(cell);
+                    board.cell(std::array::from_fn(|axis| {
+                        let coordinate = coordinates[permutation[axis]];
+                        if reversals & (0b100 >> axis) != 0 {
+                            lengths[axis] - 1 - coordinate
+                        } else {
+                            coordinate
+                        }
+                    }))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::local_search::Rng;
+
+    #[test]
+    fn the_board_has_its_shapes_symmetries() {
+        assert_eq!(Symmetries::new(&Board::new(5)).len(), 7);
+        assert_eq!(Symmetries::new(&Board::rectangle(3, 5)).len(), 3);
+        assert_eq!(Symmetries::new(&Board::cuboid(3, 3, 3)).len(), 47);
+    }
+
+    #[test]
+    fn canonical_grids_are_their_own_canonical_image() {
+        let mut rng = Rng::new(7);
+        for board in [
+            Board::new(5),
+            Board::rectangle(3, 5),
+            Board::cuboid(3, 3, 3),
+            Board::new(4).with_topology(Topology::Torus),
+        ] {
+            let symmetries = Symmetries::new(&board);
+            let mut canonical_qty = 0;
+            for _ in 0..2000 {
+                let mut grid: Grid<1> = Grid::empty();
+                for cell in 0..board.size {
+                    if rng.below(2) == 1 {
+                        grid.set(cell);
+                    }
+                }
+                let is_canonical = symmetries.is_canonical(&grid);
+                assert_eq!(
+                    is_canonical,
+                    symmetries.canonical(&grid) == grid,
+                    "{:?}",
+                    board
+                );
+                canonical_qty += is_canonical as u32;
+            }
+            assert!(canonical_qty > 0, "{:?}", board);
+        }
+    }
+}
@@ -0,0 +1,299 @@
+/**
+ * Upper bounds on a(..); so that the searches can start from the tightest, rather than from size - side + 1.
+ *
+ * Every argument is a lower bound on how many cells must be cleared, since each square needs a cleared corner:
+ *
+ * - The diagonal: the squares along the diagonal that share no corners (see Board::solution_is_possible_depth).
+ *   A pattern or the cubes need not include those squares, so the diagonal only applies to the square families;
+ *   on a solid board it is of the first layer alone.
+ * - A packing: any set of squares that share no corners needs one cleared cell per square; packed greedily.
+ * - The LP relaxation: the fewest cells that can be cleared fractionally, so that each square has a total of 1 cleared.
+ *   By duality that is the most squares that can be packed fractionally; so it is always at least the packing.
+ * - The recursion: deleting the last row & column of a grid leaves a square-free grid of side n - 1;
+ *   so a(n) <= a(n - 1) + 2n - 1, given a known a(n - 1). Likewise deleting the extra row (or column) of an
+ *   n×(n + 1) board gives a(n, n + 1) <= a(n) + n. The known terms are only of the axis-parallel squares,
+ *   so the recursion does not apply to the other square families, nor to solid boards.
+ */
+use std::fmt;
+
+use crate::board::Board;
+use crate::grid::Grid;
+use crate::packing::packing_bound;
+use crate::squares::SquareFamily;
+
+/**
+ * a(1..=8); as found by every one of the exact solvers.
+ */
+pub const KNOWN_TERMS: [u32; 8] = [1, 3, 7, 12, 17, 24, 32, 41];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Argument {
+    Diagonal,
+    Packing,
+    LinearProgram,
+    Recursion,
+}
+
+impl fmt::Display for Argument {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Argument::Diagonal => "diagonal",
+            Argument::Packing => "packing of squares",
+            Argument::LinearProgram => "LP relaxation",
+            Argument::Recursion => "recursion from a smaller board",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+pub struct UpperBound {
+    /**
+     * The tightest of the bounds.
+     */
+    pub value: u32,
+    /**
+     * The argument that gave it; the first in the order of Argument, when several tie.
+     */
+    pub argument: Argument,
+    /**
+     * Every argument that applies to the board, & its bound.
+     */
+    pub bounds: Vec<(Argument, u32)>,
+}
+
+/**
+ * Combine every argument that applies to the board.
+ */
+pub fn upper_bound<const W: usize>(board: &Board, squares: &[Grid<W>]) -> UpperBound {
+    let mut bounds = Vec::new();
+    if matches!(
+        board.family,
+        SquareFamily::AxisParallel | SquareFamily::AllOrientations
+    ) {
+        bounds.push((Argument::Diagonal, board.solution_is_possible_depth()));
+    }
+    bounds.push((Argument::Packing, board.size - packing(squares)));
+    if (board.size as usize) * (squares.len() + board.size as usize) <= MAX_TABLEAU_ENTRIES {
+        bounds.push((
+            Argument::LinearProgram,
+            board.size - fractional_packing(board, squares),
+        ));
+    }
+    let (shorter, longer) = (board.shorter_side(), board.height.max(board.width));
+    let recursion = match (&board.family, longer - shorter) {
+        _ if board.is_solid() => None,
+        (SquareFamily::AxisParallel, 0) => {
+            known_term(shorter - 1).map(|term| term + 2 * shorter - 1)
+        }
+        (SquareFamily::AxisParallel, 1) => known_term(shorter).map(|term| term + shorter),
+        _ => None,
+    };
+    if let Some(recursion) = recursion {
+        bounds.push((Argument::Recursion, recursion));
+    }
+
+    let &(argument, value) = bounds
+        .iter()
+        .min_by_key(|(_, value)| *value)
+        .expect("The packing always applies.");
+    UpperBound {
+        value,
+        argument,
+        bounds,
+    }
+}
+
+/**
+ * a(length) of the square board, if it is known.
+ */
+fn known_term(length: u32) -> Option<u32> {
+    (length as usize)
+        .checked_sub(1)
+        .and_then(|index| KNOWN_TERMS.get(index))
+        .copied()
+}
+
+//---------
+// Packing:
+//---------
+
+/**
+ * A greedy packing of squares that share no corners, with every cell still open; the smallest squares first,
+ * since they block the fewest others. A square's size is taken as the span between its first & last corners;
+ * which orders the axis-parallel squares by side.
+ */
+fn packing<const W: usize>(squares: &[Grid<W>]) -> u32 {
+    let mut by_side: Vec<Grid<W>> = squares.to_vec();
+    by_side.sort_by_key(|square| {
+        let corners: Vec<u32> = square.ones().collect();
+        corners[corners.len() - 1] - corners[0]
+    });
+    packing_bound(&by_side, &Grid::empty(), &!Grid::empty())
+}
+
+//----------------
+// LP Relaxation:
+//----------------
+
+/**
+ * The tolerance of the simplex method's comparisons.
+ */
+const EPSILON: f64 = 1e-9;
+
+/**
+ * The largest tableau that the LP relaxation is solved on. Each pivot sweeps the whole tableau, so the solid boards'
+ * thousands of cells & squares would take far longer than the search; those are only bounded by the packing.
+ */
+const MAX_TABLEAU_ENTRIES: usize = 4_000_000;
+
+/**
+ * The fewest cells that must be cleared, by the LP relaxation; rounded up.
+ *
+ * Solves the dual: maximise the sum of z over the squares, such that each cell is a corner of squares whose z sum to <= 1.
+ * Every constraint is <= 1, so the slack variables are a feasible first basis; the tableau is pivoted until optimal.
+ *
+ * Any feasible z is a valid bound, whether optimal or not. So, in case rounding errors left z slightly infeasible,
+ * it is scaled down until no cell's sum is over 1, before it is summed.
+ */
+fn fractional_packing<const W: usize>(board: &Board, squares: &[Grid<W>]) -> u32 {
+    let row_qty = board.size as usize;
+    let column_qty = squares.len() + row_qty;
+    let width = column_qty + 1;
+
+    // The constraint rows, each with a slack variable & the right hand side in the last column:
+    let mut tableau = vec![0.0; row_qty * width];
+    for (column, square) in squares.iter().enumerate() {
+        for corner in square.ones() {
+            tableau[corner as usize * width + column] = 1.0;
+        }
+    }
+    for row in 0..row_qty {
+        tableau[row * width + squares.len() + row] = 1.0;
+        tableau[row * width + column_qty] = 1.0;
+    }
+    let mut reduced_costs: Vec<f64> = (0..column_qty)
+        .map(|column| if column < squares.len() { 1.0 } else { 0.0 })
+        .collect();
+    let mut basis: Vec<usize> = (squares.len()..column_qty).collect();
+
+    // Dantzig's rule; or Bland's rule after enough pivots that the method may be cycling:
+    let bland_after = 50 * width;
+    for pivot_qty in 0.. {
+        let entering = if pivot_qty < bland_after {
+            (0..column_qty)
+                .filter(|&column| reduced_costs[column] > EPSILON)
+                .max_by(|&a, &b| reduced_costs[a].total_cmp(&reduced_costs[b]))
+        } else {
+            (0..column_qty).find(|&column| reduced_costs[column] > EPSILON)
+        };
+        let Some(entering) = entering else {
+            break;
+        };
+
+        let leaving = (0..row_qty)
+            .filter(|&row| tableau[row * width + entering] > EPSILON)
+            .min_by(|&a, &b| {
+                let ratio = |row: usize| {
+                    tableau[row * width + column_qty] / tableau[row * width + entering]
+                };
+                ratio(a).total_cmp(&ratio(b)).then(basis[a].cmp(&basis[b]))
+            })
+            .expect("The packing is bounded, since each z is at most 1.");
+
+        pivot(&mut tableau, width, leaving, entering);
+        let factor = reduced_costs[entering];
+        for column in 0..column_qty {
+            reduced_costs[column] -= factor * tableau[leaving * width + column];
+        }
+        basis[leaving] = entering;
+    }
+
+    let mut packing = vec![0.0; squares.len()];
+    for (row, &column) in basis.iter().enumerate() {
+        if column < squares.len() {
+            packing[column] = tableau[row * width + column_qty].max(0.0);
+        }
+    }
+    let mut loads = vec![0.0; row_qty];
+    for (square, z) in squares.iter().zip(packing.iter()) {
+        for corner in square.ones() {
+            loads[corner as usize] += z;
+        }
+    }
+    let scale = loads.iter().copied().fold(1.0, f64::max);
+    let packed: f64 = packing.iter().sum::<f64>() / scale;
+    (packed - 1e-6).ceil().max(0.0) as u32
+}
+
+/**
+ * Pivot the tableau on (row, column): scale the row so that the column is 1, then eliminate the column from every other row.
+ */
+fn pivot(tableau: &mut [f64], width: usize, row: usize, column: usize) {
+    let pivot_value = tableau[row * width + column];
+    for value in tableau[row * width..(row + 1) * width].iter_mut() {
+        *value /= pivot_value;
+    }
+    let pivot_row = tableau[row * width..(row + 1) * width].to_vec();
+
+    for (other_row, values) in tableau.chunks_mut(width).enumerate() {
+        let factor = values[column];
+        if other_row == row || factor.abs() <= EPSILON {
+            continue;
+        }
+        for (value, pivot_value) in values.iter_mut().zip(pivot_row.iter()) {
+            *value -= factor * pivot_value;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Topology;
+    use crate::branch_and_bound;
+    use crate::squares::get_squares;
+
+    #[test]
+    fn every_bound_is_at_least_the_known_term() {
+        for (length, &term) in (1..).zip(KNOWN_TERMS.iter()) {
+            let board = Board::new(length);
+            let upper_bound = upper_bound::<1>(&board, &get_squares(&board));
+
+            for &(argument, value) in upper_bound.bounds.iter() {
+                assert!(
+                    value >= term,
+                    "n = {}: the {} gives {}",
+                    length,
+                    argument,
+                    value
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn every_bound_is_at_least_the_optimum() {
+        for board in [
+            Board::rectangle(3, 5),
+            Board::rectangle(4, 5),
+            Board::rectangle(2, 7),
+            Board::new(5).with_topology(Topology::Torus),
+            Board::rectangle(4, 6).with_topology(Topology::Torus),
+            Board::new(5).with_family(SquareFamily::AllOrientations),
+            Board::rectangle(4, 5).with_family(SquareFamily::AllOrientations),
+        ] {
+            let squares: Vec<Grid<1>> = get_squares(&board);
+            let optimum = branch_and_bound::solve::<1>(&board).count_ones();
+
+            for &(argument, value) in upper_bound(&board, &squares).bounds.iter() {
+                assert!(
+                    value >= optimum,
+                    "{:?}: the {} gives {}",
+                    board,
+                    argument,
+                    value
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,232 @@
+/**
+ * An incremental count of the squares in a grid; for the searches that change one cell at a time.
+ *
+ * grid_contains_squares rescans every square, for every grid. Instead, each square's painted corners are counted;
+ * & so is, for each cell, the number of squares whose other corners are all painted: the squares that painting it
+ * would complete, or (once it is painted) the complete squares that it is a corner of.
+ * A "square" is any constraint from get_squares; so a pattern's placements may have any number of corners.
+ * Flipping a cell only changes the squares with a corner on it; so it costs O(squares through that cell),
+ * via an index from each cell to those squares.
+ */
+use crate::board::Board;
+use crate::grid::Grid;
+
+pub struct Violations<const W: usize> {
+    /**
+     * The corners of every square, one after another; so that they are not found by scanning its words on every flip.
+     * The corners of square n are corners[corner_starts[n]..corner_starts[n + 1]].
+     */
+    corners: Vec<u32>,
+    corner_starts: Vec<usize>,
+    /**
+     * squares_through[n] are the indices of the squares with a corner on cell n.
+     */
+    squares_through: Vec<Vec<usize>>,
+    grid: Grid<W>,
+    /**
+     * painted_corners[n] is the number of square n's corners that are painted; a pattern may have any number of cells.
+     */
+    painted_corners: Vec<u32>,
+    /**
+     * completions[n] is the number of squares through cell n whose other corners are all painted.
+     */
+    completions: Vec<u32>,
+    complete_qty: u32,
+}
+
+impl<const W: usize> Violations<W> {
+    /**
+     * The counts for the empty grid; where no square has a painted corner.
+     */
+    pub fn new(board: &Board, squares: &[Grid<W>]) -> Violations<W> {
+        let mut squares_through: Vec<Vec<usize>> = vec![Vec::new(); board.size as usize];
+        for (index, square) in squares.iter().enumerate() {
+            for corner in square.ones() {
+                squares_through[corner as usize].push(index);
+            }
+        }
+
+        let mut corners = Vec::new();
+        let mut corner_starts = vec![0];
+        for square in squares.iter() {
+            corners.extend(square.ones());
+            corner_starts.push(corners.len());
+        }
+
+        Violations {
+            corners,
+            corner_starts,
+            squares_through,
+            grid: Grid::empty(),
+            painted_corners: vec![0; squares.len()],
+            completions: vec![0; board.size as usize],
+            complete_qty: 0,
+        }
+    }
+
+    pub fn grid(&self) -> Grid<W> {
+        self.grid
+    }
+
+    /**
+     * The number of squares in the grid; so the grid is square-free when it is 0.
+     */
+    pub fn complete_qty(&self) -> u32 {
+        self.complete_qty
+    }
+
+    /**
+     * The number of squares that painting cell would complete; or, if it is painted, the complete squares through it.
+     */
+    pub fn completions(&self, cell: u32) -> u32 {
+        self.completions[cell as usize]
+    }
+
+    pub fn squares_through(&self, cell: u32) -> &[usize] {
+        &self.squares_through[cell as usize]
+    }
+
+    pub fn is_complete(&self, index: usize) -> bool {
+        self.painted_corners[index] as usize == self.corners_of(index).len()
+    }
+
+    fn corners_of(&self, index: usize) -> &[u32] {
+        &self.corners[self.corner_starts[index]..self.corner_starts[index + 1]]
+    }
+
+    pub fn paint(&mut self, cell: u32) {
+        if !self.grid.test(cell) {
+            self.flip(cell);
+        }
+    }
+
+    pub fn clear(&mut self, cell: u32) {
+        if self.grid.test(cell) {
+            self.flip(cell);
+        }
+    }
+
+    /**
+     * Paint the cell if it is cleared, or clear it if it is painted; updating every count that it changes.
+     *
+     * The cell's own completions never change, since only its squares' other corners count toward them.
+     * Only a square with all but one or all of its corners painted (counting the cell) changes anyone's completions:
+     * with all but one, its one cleared corner gains (or loses) a completion; with all, the square is complete,
+     * & each of its other corners gains (or loses) one.
+     */
+    pub fn flip(&mut self, cell: u32) {
+        let painting = !self.grid.test(cell);
+        if painting {
+            self.grid.set(cell);
+        } else {
+            self.grid.clear(cell);
+        }
+        // Each count changes by +1 when painting, or by -1 (as a wrapping add) when clearing:
+        let change = if painting { 1 } else { u32::MAX };
+
+        let Violations {
+            corners,
+            corner_starts,
+            squares_through,
+            grid,
+            painted_corners,
+            completions,
+            complete_qty,
+            ..
+        } = self;
+        for &index in squares_through[cell as usize].iter() {
+            // The painted corners of the square, while the cell is painted:
+            let painted_with_cell = if painting {
+                painted_corners[index] += 1;
+                painted_corners[index]
+            } else {
+                painted_corners[index] -= 1;
+                painted_corners[index] + 1
+            };
+
+            let corners = &corners[corner_starts[index]..corner_starts[index + 1]];
+            let corner_qty = corners.len() as u32;
+            if painted_with_cell + 1 == corner_qty {
+                for &corner in corners.iter() {
+                    if corner != cell && !grid.test(corner) {
+                        completions[corner as usize] =
+                            completions[corner as usize].wrapping_add(change);
+                    }
+                }
+            } else if painted_with_cell == corner_qty {
+                for &corner in corners.iter() {
+                    if corner != cell {
+                        completions[corner as usize] =
+                            completions[corner as usize].wrapping_add(change);
+                    }
+                }
+                *complete_qty = complete_qty.wrapping_add(change);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::local_search::Rng;
+    use crate::pattern::Pattern;
+    use crate::squares::{get_squares, SquareFamily};
+
+    use std::sync::Arc;
+
+    #[test]
+    fn flips_match_a_full_recount() {
+        let board = Board::new(6);
+        let squares: Vec<Grid<1>> = get_squares(&board);
+        let mut violations = Violations::new(&board, &squares);
+        let mut rng = Rng::new(1);
+
+        for _ in 0..2_000 {
+            violations.flip(rng.below(board.size as u64) as u32);
+            let grid = violations.grid();
+
+            let complete_qty = squares
+                .iter()
+                .filter(|square| square.is_subset_of(&grid))
+                .count();
+            assert_eq!(violations.complete_qty(), complete_qty as u32);
+            for cell in 0..board.size {
+                let with_cell = grid | Grid::from_bit(cell);
+                let completions = squares
+                    .iter()
+                    .filter(|square| square.test(cell) && square.is_subset_of(&with_cell))
+                    .count();
+                assert_eq!(violations.completions(cell), completions as u32);
+            }
+        }
+    }
+
+    /**
+     * A whole row of 256 cells; more corners than a byte can count.
+     */
+    #[test]
+    fn patterns_of_many_cells_are_counted() {
+        let cells: Vec<String> = (0..256).map(|column| format!("0,{}", column)).collect();
+        let pattern = Pattern::parse(&format!(
+            "cells={}\ntransforms=translation",
+            cells.join(" ")
+        ))
+        .unwrap();
+        let board = Board::rectangle(4, 256).with_family(SquareFamily::Pattern(Arc::new(pattern)));
+        let squares: Vec<Grid<16>> = get_squares(&board);
+        let mut violations = Violations::new(&board, &squares);
+        assert_eq!(squares.len(), 4);
+
+        for cell in 0..255 {
+            violations.paint(cell);
+        }
+        assert_eq!(violations.complete_qty(), 0);
+        assert_eq!(violations.completions(255), 1);
+        violations.paint(255);
+        assert_eq!(violations.complete_qty(), 1);
+        violations.clear(0);
+        assert_eq!(violations.complete_qty(), 0);
+        assert_eq!(violations.completions(0), 1);
+    }
+}
@@ -1,82 +1,438 @@
-/**
- * Author: Kier Palin.
- *
- * A solution to the OEIS/A227133 problem.
- *
- * Where 1 is the tile to maximise.
- * A 'unit square' is a grid that contains only the 1 tiles in its corners, all else are 0;
- * hence representing an atomic, illegal, state for a grid.
- *
- * These can be combined together via Bitwise ORing all grids that have their nth bit set.
- * This will create a 'dependency_map'; outlining the relationship between a cell and
- * the corners of the squares that use it (depend upon it).
- * These dependency_maps can be pre-calculated & make checking a grid for squares trivial (simple bitwise AND).
- *
- * Basic Algorithm:
- *
- * A grid can be modelled 3-dimensionally:
- * Where the 3rd dimension is a list of the corners of the unit squares that overlap on each cell.
- * Since all corners of the squares are represented as 1 tiles this 3rd dimension can simply be expressed as a count.
- * All of the 1's that repsent the corners of the possible squares accumulated.
- *
- * The peaks in this heatmap are the cells with the greatest value; the 'hottest' ones;
- * They are the cells that are used as corners by the most number of squares;
- * Hence setting the hottest cell to a 0 is neccessarily the most efficient use of the 0 tiles.
- * There are multiple, distinct, solutions to most grids, since the most efficient usage of the 0 tile is also relative
- * to the previously used tiles.
- *
- * Since the search is for the grid with the greatest number of 1 tiles: grid checking should start from the top down.
- * Where the 'top' are the grids with the most number of 1 tiles; the quantity of 1 tiles in a grid is its 'popcount'
- * Hence the problem can be approached as breadth-first search; where all grids with popcount k are checked,
- * before moving onto all grids with popcount k-1; these grids are the children of the above grids & can be calculated via dependency maps.
- *
- * However, since it is impossible for a grid to contain > (GRID_SIZE - GRID_LENGTH + 1) number of 1 tiles;
- * since there would be too few 0 tiles to fill the squares that form along a diagonal - it is not neccessary to check popcounts above this threshold.
- */
-mod state;
-use state::*;
-
-mod square_utils;
-
-mod hca_utils;
-use lazy_static::lazy_static;
-
-use std::{collections::VecDeque, time::Instant};
-
-pub const GRID_LENGTH: i8 = 3;
-pub const GRID_SIZE: i8 = GRID_LENGTH * GRID_LENGTH;
-pub const SOLUTION_IS_POSSIBLE_DEPTH: u8 = (GRID_SIZE - GRID_LENGTH + 1) as u8;
-
-lazy_static! {
-    static ref SQUARES: Vec<u128> = square_utils::get_squares();
-    static ref SQUARES_AS_BITLIST: Vec<Vec<i8>> = square_utils::get_squares_as_bitlist();
-    static ref DEPENDENCY_MAPS: Vec<Vec<i8>> = hca_utils::get_dependency_maps();
-}
-
-fn search(state_queue: &mut VecDeque<State>, current_depth: u8) -> State {
-    let mut next_state_queue: VecDeque<State> = VecDeque::new();
-    let mut current_state: State;
-
-    while !state_queue.is_empty() {
-        current_state = state_queue.pop_front().unwrap();
-
-        // Valid Solution:
-        if current_depth <= SOLUTION_IS_POSSIBLE_DEPTH && !current_state.contains_squares() {
-            return current_state;
-        }
-        // Not valid, or not at depth to search yet; just add child states:
-        else {
-            for child_state in current_state.get_children() {
-                next_state_queue.push_back(child_state);
-            }
-        }
-    }
-    search(&mut next_state_queue, current_depth - 1)
-}
-
-fn main() {
-    let now = Instant::now();
-    let mut state_queue: VecDeque<State> = VecDeque::from(vec![State::default()]);
-    search(&mut state_queue, GRID_SIZE as u8).print_grid();
-    println!("Took {:?} to solve.", now.elapsed());
-}
+/**
+ * Author: Kier Palin.
+ *
+ * A solution to the OEIS/A227133 problem.
+ *
+ * Where 1 is the tile to maximise.
+ * A 'unit square' is a grid that contains only the 1 tiles in its corners, all else are 0;
+ * hence representing an atomic, illegal, state for a grid.
+ *
+ * These can be combined together via Bitwise ORing all grids that have their nth bit set.
+ * This will create a 'dependency_map'; outlining the relationship between a cell and
+ * the corners of the squares that use it (depend upon it).
+ * These dependency_maps can be pre-calculated & make checking a grid for squares trivial (simple bitwise AND).
+ *
+ * Basic Algorithm:
+ *
+ * A grid can be modelled 3-dimensionally:
+ * Where the 3rd dimension is a list of the corners of the unit squares that overlap on each cell.
+ * Since all corners of the squares are represented as 1 tiles this 3rd dimension can simply be expressed as a count.
+ * All of the 1's that repsent the corners of the possible squares accumulated.
+ *
+ * The peaks in this heatmap are the cells with the greatest value; the 'hottest' ones;
+ * They are the cells that are used as corners by the most number of squares;
+ * Hence setting the hottest cell to a 0 is neccessarily the most efficient use of the 0 tiles.
+ * There are multiple, distinct, solutions to most grids, since the most efficient usage of the 0 tile is also relative
+ * to the previously used tiles.
+ *
+ * Since the search is for the grid with the greatest number of 1 tiles: grid checking should start from the top down.
+ * Where the 'top' are the grids with the most number of 1 tiles; the quantity of 1 tiles in a grid is its 'popcount'
+ * Hence the problem can be approached as breadth-first search; where all grids with popcount k are checked,
+ * before moving onto all grids with popcount k-1; these grids are the children of the above grids & can be calculated via dependency maps.
+ *
+ * However, since it is impossible for a grid to contain > (GRID_SIZE - GRID_LENGTH + 1) number of 1 tiles;
+ * since there would be too few 0 tiles to fill the squares that form along a diagonal - it is not neccessary to check popcounts above this threshold.
+ * The tighter bounds of a227133_core::upper_bound lower the threshold further; the grids above it are only expanded.
+ *
+ * Clearing only the peaks is a heuristic: nothing guarantees that the best grid is reached that way.
+ * So by default the result is reported as a lower bound, with the gap to an upper bound on a(n).
+ * In the exact mode the heuristic's grid is only the one to beat: each state then clears every corner of a square
+ * that is still in its grid (one of them must be cleared), & states that cannot beat the best grid so far are pruned.
+ * The first square-free state found is then optimal; or, once no state is left, the heuristic's grid was.
+ */
+mod state;
+use state::State;
+
+mod hca_utils;
+
+mod tables;
+use tables::Tables;
+
+extern crate a227133_core;
+use a227133_core::local_search::{anneal, Annealing};
+use a227133_core::packing::packing_bound;
+use a227133_core::pattern::Pattern;
+use a227133_core::progress::{Observer, Progress, Reporter, StatusLine, PROGRESS_INTERVAL};
+use a227133_core::{with_grid_words, Board, Grid, SquareFamily, Topology};
+
+use rayon::prelude::*;
+
+use std::collections::HashSet;
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+use std::{env, process};
+
+/**
+ * Check each state of the current depth (popcount); returning a square-free one.
+ * Otherwise, move on to the children of them all: the states with a peak cleared.
+ *
+ * Each level's frontier is checked & expanded in parallel; the check stops as soon as any worker finds a solution.
+ * Which solution that is depends upon the scheduling, unless deterministic; then it is the first in the frontier.
+ */
+fn search<const W: usize>(
+    tables: &Tables<W>,
+    mut frontier: Vec<State<W>>,
+    mut current_depth: u32,
+    deterministic: bool,
+    reporter: &Reporter,
+) -> State<W> {
+    loop {
+        let level = level_progress(current_depth, &frontier);
+        reporter.start_level(level.clone());
+
+        // Valid Solution:
+        if current_depth <= tables.upper_bound.value {
+            if let Some(solution) =
+                find_square_free(tables, &frontier, deterministic, reporter, &level)
+            {
+                reporter.finish_level(level);
+                return solution;
+            }
+        }
+
+        // Not valid, or not at depth to search yet; just add child states:
+        let children: Vec<State<W>> = frontier
+            .par_iter()
+            .flat_map_iter(|state| state.get_children(tables))
+            .collect();
+        reporter.finish_level(level);
+        frontier = deduplicate(tables, children);
+        current_depth -= 1;
+    }
+}
+
+/**
+ * Like search, but the children clear every corner of a square still in the grid (see State::get_exact_children);
+ * & any child that cannot beat the incumbent, even if the fewest further cells it could need were cleared, is pruned.
+ */
+fn search_exact<const W: usize>(
+    tables: &Tables<W>,
+    mut frontier: Vec<State<W>>,
+    incumbent: State<W>,
+    deterministic: bool,
+    reporter: &Reporter,
+) -> State<W> {
+    let incumbent_cleared_qty = tables.board.size - incumbent.popcount(&tables.board);
+    // Each child clears one more cell than its parent:
+    let mut current_depth = tables.board.size;
+    loop {
+        let level = level_progress(current_depth, &frontier);
+        reporter.start_level(level.clone());
+
+        if let Some(solution) = find_square_free(tables, &frontier, deterministic, reporter, &level)
+        {
+            reporter.finish_level(level);
+            return solution;
+        }
+
+        let children: Vec<State<W>> = frontier
+            .par_iter()
+            .flat_map_iter(|state| state.get_exact_children(tables))
+            .filter(|child_state| {
+                let cleared_cells = child_state.cleared_cells();
+                let fewest_cleared_qty = cleared_cells.count_ones()
+                    + packing_bound(&tables.squares, &cleared_cells, &!cleared_cells);
+                fewest_cleared_qty < incumbent_cleared_qty
+            })
+            .collect();
+        reporter.finish_level(level);
+
+        if children.is_empty() {
+            return incumbent;
+        }
+        frontier = deduplicate(tables, children);
+        current_depth -= 1;
+    }
+}
+
+/**
+ * The progress at the start of a level; every state in the frontier is a candidate.
+ */
+fn level_progress<const W: usize>(current_depth: u32, frontier: &[State<W>]) -> Progress {
+    Progress {
+        popcount: current_depth,
+        total: Some(frontier.len() as u128),
+        frontier_size: Some(frontier.len()),
+        ..Progress::default()
+    }
+}
+
+fn find_square_free<const W: usize>(
+    tables: &Tables<W>,
+    frontier: &[State<W>],
+    deterministic: bool,
+    reporter: &Reporter,
+    level: &Progress,
+) -> Option<State<W>> {
+    let checked_qty = AtomicU64::new(0);
+    let is_square_free = |state: &&State<W>| {
+        let checked = checked_qty.fetch_add(1, Ordering::Relaxed) + 1;
+        reporter.maybe_report(|| Progress {
+            checked: checked as u128,
+            ..level.clone()
+        });
+        !state.contains_squares(tables)
+    };
+    if deterministic {
+        frontier.par_iter().find_first(is_square_free).copied()
+    } else {
+        frontier.par_iter().find_any(is_square_free).copied()
+    }
+}
+
+/**
+ * Clearing peak a then b reaches the same grid as clearing b then a; as do the rotations & reflections of a grid.
+ * So each child is keyed by the canonical form of its cleared cells, & only the first child with each key is kept.
+ *
+ * The heatmap only depends upon which cells are cleared, not the order they were cleared in;
+ * so states with the same key are the same grid, or images of one another.
+ * The keys are calculated in parallel, but kept in order; so the next frontier is the same from run to run.
+ */
+fn deduplicate<const W: usize>(tables: &Tables<W>, children: Vec<State<W>>) -> Vec<State<W>> {
+    let keys: Vec<Grid<W>> = children
+        .par_iter()
+        .map(|child_state| tables.symmetries.canonical(&child_state.cleared_cells()))
+        .collect();
+
+    let mut visited: HashSet<Grid<W>> = HashSet::new();
+    children
+        .into_iter()
+        .zip(keys)
+        .filter(|(_, key)| visited.insert(*key))
+        .map(|(child_state, _)| child_state)
+        .collect()
+}
+
+/**
+ * The heatmap collapse heuristic; its grid is square-free, but may not be optimal.
+ */
+fn solve_heuristic<const W: usize>(
+    tables: &Tables<W>,
+    deterministic: bool,
+    reporter: &Reporter,
+) -> State<W> {
+    search(
+        tables,
+        vec![State::initial(tables)],
+        tables.board.size,
+        deterministic,
+        reporter,
+    )
+}
+
+/**
+ * An optimal grid; starting from the heuristic's.
+ */
+fn solve_exact<const W: usize>(
+    tables: &Tables<W>,
+    deterministic: bool,
+    reporter: &Reporter,
+) -> State<W> {
+    // The better of the heuristic's grid & the local search's:
+    let heuristic = solve_heuristic(tables, deterministic, reporter);
+    let annealed = anneal(
+        &tables.board,
+        &tables.squares,
+        &Annealing::default(),
+        |_| {},
+    );
+    let incumbent = if annealed.count_ones() > heuristic.popcount(&tables.board) {
+        State::from_painted_cells(tables, annealed)
+    } else {
+        heuristic
+    };
+    // Nothing can beat a grid that meets the upper bound:
+    if incumbent.popcount(&tables.board) == tables.upper_bound.value {
+        return incumbent;
+    }
+    search_exact(
+        tables,
+        vec![State::initial(tables)],
+        incumbent,
+        deterministic,
+        reporter,
+    )
+}
+
+fn solve_with_width<const W: usize>(board: Board, exact: bool, deterministic: bool, quiet: bool) {
+    let now = Instant::now();
+    let tables: Tables<W> = Tables::new(board.clone());
+
+    // The status line is only drawn for a person watching the terminal:
+    let status_line = StatusLine::default();
+    let observers: Vec<&dyn Observer> = if quiet || !io::stderr().is_terminal() {
+        Vec::new()
+    } else {
+        vec![&status_line]
+    };
+    let reporter = Reporter::new(observers, PROGRESS_INTERVAL);
+
+    if exact {
+        let solution = solve_exact(&tables, deterministic, &reporter);
+        println!("F({}) = {}.", board, solution.popcount(&tables.board));
+        solution.print_grid(&tables.board);
+    } else {
+        let solution = solve_heuristic(&tables, deterministic, &reporter);
+        let upper_bound = &tables.upper_bound;
+        println!(
+            "F({}) >= {}; the heatmap collapse is a heuristic, so this is a lower bound.",
+            board,
+            solution.popcount(&tables.board)
+        );
+        match upper_bound.value - solution.popcount(&tables.board) {
+            0 => println!(
+                "F({}) <= {}, by the {}; so the bound is exact.",
+                board, upper_bound.value, upper_bound.argument
+            ),
+            gap => println!(
+                "F({}) <= {}, by the {}; a gap of at most {}. Pass --exact to close it.",
+                board, upper_bound.value, upper_bound.argument, gap
+            ),
+        }
+        solution.print_grid(&tables.board);
+    }
+    println!("Took {:?} to solve.", now.elapsed());
+}
+
+const USAGE: &str = "Usage: rust_solution <n | m>x<n | m>x<n>x<l> [--exact] [--deterministic] [--quiet] [--squares <axis-parallel|all-orientations|cubes> | --pattern <pattern file>] [--torus]";
+
+fn main() {
+    let arguments: Vec<String> = env::args().skip(1).collect();
+
+    let exit_with = |error: String| -> ! {
+        eprintln!("{}", error);
+        process::exit(1);
+    };
+    let mut board: Option<&String> = None;
+    let mut exact = false;
+    // Return the first solution in frontier order, rather than whichever a worker finds first:
+    let mut deterministic = false;
+    let mut quiet = false;
+    let mut family = SquareFamily::AxisParallel;
+    let mut topology = Topology::Plane;
+    let mut arguments = arguments.iter();
+    while let Some(argument) = arguments.next() {
+        match argument.as_str() {
+            "--exact" => exact = true,
+            "--deterministic" => deterministic = true,
+            "--quiet" => quiet = true,
+            "--torus" => topology = Topology::Torus,
+            "--squares" => {
+                let name = arguments
+                    .next()
+                    .unwrap_or_else(|| exit_with(USAGE.to_string()));
+                family = SquareFamily::parse(name)
+                    .unwrap_or_else(|error| exit_with(format!("{}\n{}", error, USAGE)));
+            }
+            "--pattern" => {
+                let path = arguments
+                    .next()
+                    .unwrap_or_else(|| exit_with(USAGE.to_string()));
+                let pattern = fs::read_to_string(path)
+                    .map_err(|error| format!("Could not read pattern {}: {}", path, error))
+                    .and_then(|spec| Pattern::parse(&spec))
+                    .unwrap_or_else(|error| exit_with(error));
+                family = SquareFamily::Pattern(Arc::new(pattern));
+            }
+            _ if board.is_none() && !argument.starts_with("--") => board = Some(argument),
+            _ => exit_with(USAGE.to_string()),
+        }
+    }
+    let board = board.unwrap_or_else(|| exit_with(USAGE.to_string()));
+    let board = Board::parse(board)
+        .unwrap_or_else(|error| exit_with(format!("{}\n{}", error, USAGE)))
+        .with_family(family)
+        .with_topology(topology);
+    board
+        .validate()
+        .unwrap_or_else(|error| exit_with(format!("{}\n{}", error, USAGE)));
+
+    with_grid_words!(board.size, W => solve_with_width::<W>(board, exact, deterministic, quiet));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use a227133_core::upper_bound::KNOWN_TERMS;
+    use naive_solution::search::{search, Level};
+
+    /**
+     * a(..) of the board, as naive_solution's sweep finds it; from the diagonal bound down,
+     * so that neither the sweep nor the checks against it lean on the upper bound.
+     */
+    fn naive_term(tables: &Tables<1>) -> u32 {
+        let level = Level::new(&tables.board, tables.board.solution_is_possible_depth());
+        search(
+            &tables.board,
+            &tables.squares,
+            level,
+            Grid::empty(),
+            None,
+            &Reporter::silent(),
+        )
+        .count_ones()
+    }
+
+    #[test]
+    fn exact_mode_matches_the_naive_solution() {
+        for length in 2..=5 {
+            let tables: Tables<1> = Tables::new(Board::new(length));
+            let solution = solve_exact(&tables, true, &Reporter::silent());
+
+            assert!(!solution.contains_squares(&tables));
+            assert_eq!(
+                solution.popcount(&tables.board),
+                naive_term(&tables),
+                "n = {}",
+                length
+            );
+        }
+    }
+
+    /**
+     * n = 6 is too slow for the naive sweep in a test, so is checked against the known term instead.
+     */
+    #[test]
+    fn both_modes_match_the_known_term_of_6() {
+        let tables: Tables<1> = Tables::new(Board::new(6));
+        let exact = solve_exact(&tables, true, &Reporter::silent());
+        let heuristic = solve_heuristic(&tables, true, &Reporter::silent());
+
+        assert!(!exact.contains_squares(&tables));
+        assert_eq!(exact.popcount(&tables.board), KNOWN_TERMS[5]);
+        assert!(!heuristic.contains_squares(&tables));
+        assert!(heuristic.popcount(&tables.board) <= KNOWN_TERMS[5]);
+    }
+
+    #[test]
+    fn heuristic_is_a_square_free_lower_bound() {
+        for length in 2..=5 {
+            let tables: Tables<1> = Tables::new(Board::new(length));
+            let solution = solve_heuristic(&tables, true, &Reporter::silent());
+            let term = naive_term(&tables);
+
+            assert!(!solution.contains_squares(&tables));
+            assert!(solution.popcount(&tables.board) <= term);
+            assert!(tables.upper_bound.value >= term);
+        }
+    }
+
+    #[test]
+    fn deterministic_runs_are_reproducible() {
+        for board in [Board::new(5), Board::rectangle(4, 5)] {
+            let tables: Tables<1> = Tables::new(board);
+            let first = solve_heuristic(&tables, true, &Reporter::silent());
+            let second = solve_heuristic(&tables, true, &Reporter::silent());
+
+            assert_eq!(
+                first.cleared_cells(),
+                second.cleared_cells(),
+                "{:?}",
+                tables.board
+            );
+        }
+    }
+}
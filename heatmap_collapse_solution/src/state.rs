@@ -1,135 +1,199 @@
 #![allow(unused)]
 
-use crate::{hca_utils, square_utils, DEPENDENCY_MAPS, GRID_LENGTH, GRID_SIZE, SQUARES_AS_BITLIST};
+use crate::hca_utils::{self, Heatmap};
+use crate::tables::Tables;
+use a227133_core::{grid_contains_squares, Board, Grid};
 
-use itertools::Itertools;
-use std::collections::VecDeque;
-use std::str;
-
-pub struct State {
-    heatmap: Vec<i8>,
-    heatmap_peaks: Vec<usize>,
-    depth: i8,
+/**
+ * A grid part way through the collapse.
+ * The state is a fixed size (the heatmap has a counter per bit of the grid's words), so it is Copy;
+ * a child is the parent with one cell cleared & the heatmap updated in place; nothing is allocated.
+ */
+#[derive(Clone, Copy)]
+pub struct State<const W: usize> {
+    cleared_cells: Grid<W>,
+    heatmap: Heatmap<W>,
+    heatmap_peaks: Grid<W>,
 }
 
-impl State {
-    pub fn new(heatmap: Vec<i8>, heatmap_peaks: Vec<usize>, depth: i8) -> State {
+impl<const W: usize> State<W> {
+    /**
+     * The state of the grid with every cell painted.
+     */
+    pub fn initial(tables: &Tables<W>) -> State<W> {
+        let heatmap = hca_utils::get_initial_heatmap(&tables.squares);
         State {
+            cleared_cells: Grid::empty(),
             heatmap,
-            heatmap_peaks,
-            depth,
+            heatmap_peaks: hca_utils::get_peaks(&tables.board, &heatmap),
         }
     }
 
+    /**
+     * The state of a grid found some other way (e.g. by local search); every other cell cleared in turn.
+     */
+    pub fn from_painted_cells(tables: &Tables<W>, painted_cells: Grid<W>) -> State<W> {
+        let cleared_cells = Grid::with_lowest_bits(tables.board.size) & !painted_cells;
+        cleared_cells
+            .ones()
+            .fold(State::initial(tables), |state, cell| {
+                state.child(tables, cell)
+            })
+    }
+
     /**
      * Apply the dependency map to this state.
      * Where each dependency map corresponds to a heatmap_peak
+     *
+     * If a rotation or reflection maps this state's grid onto itself, then it also maps
+     * clearing peak p onto clearing the peak it moves p to; those two children are images of one another.
+     * So, only the peak with the smallest index in each such family is cleared.
      */
-    pub fn get_children(&mut self) -> Vec<State> {
-        let mut children: Vec<State> = Vec::new();
+    pub fn get_children(&self, tables: &Tables<W>) -> Vec<State<W>> {
+        let mut children: Vec<State<W>> = Vec::new();
+        let stabiliser = tables.symmetries.stabiliser(&self.cleared_cells);
+
+        for heatmap_peak in self.heatmap_peaks.ones() {
+            let is_smallest_image = stabiliser.iter().all(|&symmetry| {
+                tables.symmetries.transform_cell(symmetry, heatmap_peak) >= heatmap_peak
+            });
+            if !is_smallest_image {
+                continue;
+            }
 
-        for heatmap_peak in self.heatmap_peaks.iter() {
-            let child_heatmap = self.apply_dependency_map(*heatmap_peak);
-            let heatmap_peaks = hca_utils::get_peaks(&child_heatmap);
-            children.push(State::new(child_heatmap, heatmap_peaks, self.depth - 1))
+            children.push(self.child(tables, heatmap_peak));
         }
 
         children
     }
 
+    /**
+     * Clear each corner of one square that is still in the grid; unlike get_children,
+     * this never skips a grid with more painted cells, since one of those corners must be cleared.
+     * The square with the hottest corners is chosen; so the peaks are still cleared first.
+     *
+     * Only the symmetries that map both the grid & the square onto themselves are used to skip children;
+     * any other symmetry would move the clearing off of the square.
+     */
+    pub fn get_exact_children(&self, tables: &Tables<W>) -> Vec<State<W>> {
+        let square = tables
+            .squares
+            .iter()
+            .filter(|square| (**square & self.cleared_cells).is_empty())
+            .max_by_key(|square| {
+                square
+                    .ones()
+                    .map(|corner| hca_utils::heat(&self.heatmap, corner) as i32)
+                    .sum::<i32>()
+            });
+        let square = match square {
+            Some(square) => square,
+            None => return Vec::new(),
+        };
+
+        let stabiliser: Vec<usize> = tables
+            .symmetries
+            .stabiliser(&self.cleared_cells)
+            .into_iter()
+            .filter(|&symmetry| tables.symmetries.transform(symmetry, square) == *square)
+            .collect();
+
+        square
+            .ones()
+            .filter(|&corner| {
+                stabiliser
+                    .iter()
+                    .all(|&symmetry| tables.symmetries.transform_cell(symmetry, corner) >= corner)
+            })
+            .map(|corner| self.child(tables, corner))
+            .collect()
+    }
+
     /**
      * Does the grid of this state contain a square?
+     * Each square is a bitwise AND against the painted cells.
      */
-    pub fn contains_squares(&self) -> bool {
-        let grid = self.as_grid();
+    pub fn contains_squares(&self, tables: &Tables<W>) -> bool {
+        grid_contains_squares(self.painted_cells(&tables.board), &tables.squares)
+    }
 
-        for square in SQUARES_AS_BITLIST.iter() {
-            if (square_utils::square_in_grid(&grid, square)) {
-                return true;
-            }
-        }
-        false
+    /**
+     * The number of painted cells.
+     */
+    pub fn popcount(&self, board: &Board) -> u32 {
+        board.size - self.cleared_cells.count_ones()
     }
 
     /**
-     * Generate a new heatmap by applying the nth dependency_map.
-     * The nth element of the heatmap is set to the -1 sentinel value.
-     * Cells with a value less than or equal to 0 are not updated.
-     * Otherwise, the dependency_map is simply elementwise subtracted from the self.heatmap.
+     * The cells that have been cleared, as a bitboard.
      */
-    fn apply_dependency_map(&self, n: usize) -> Vec<i8> {
-        let dependency_map = &DEPENDENCY_MAPS[n];
-        let mut new_heatmap: Vec<i8> = self
-            .heatmap
-            .clone()
-            .iter()
-            .zip(dependency_map)
-            .map(|(&heatmap_element, &dependency_map_element)| {
-                if heatmap_element <= 0 {
-                    heatmap_element
-                } else {
-                    heatmap_element - dependency_map_element
-                }
-            })
-            .collect();
-        new_heatmap[n] = -1;
+    pub fn cleared_cells(&self) -> Grid<W> {
+        self.cleared_cells
+    }
 
-        new_heatmap
+    pub fn painted_cells(&self, board: &Board) -> Grid<W> {
+        Grid::with_lowest_bits(board.size) & !self.cleared_cells
     }
 
     /**
-     * Convert self.heatmap into a grid.
-     * All values <= 0 are set to 0; otherwise remain 1.
+     * The state after clearing cell n.
      */
-    fn as_grid(&self) -> Vec<i8> {
-        self.heatmap
-            .clone()
-            .iter()
-            .map(|&heatmap_element| (heatmap_element != -1) as i8)
-            .collect_vec()
+    fn child(&self, tables: &Tables<W>, n: u32) -> State<W> {
+        let mut child = *self;
+        child.apply_dependency_map(tables, n);
+        child.cleared_cells.set(n);
+        child.heatmap_peaks = hca_utils::get_peaks(&tables.board, &child.heatmap);
+        child
     }
 
     /**
-     * Display the current state & its popcount.
+     * Apply the nth dependency_map to the heatmap, in place.
+     * The nth element of the heatmap is set to the -1 sentinel value.
+     * Cells with a value less than or equal to 0 are not updated.
+     * Otherwise, every cell in the dependency_map is decremented.
      */
-    pub fn print_grid(&self) {
-        let grid = self.as_grid();
-        println!(
-            "F({}) = {}.",
-            GRID_LENGTH,
-            grid.iter().filter(|&n| *n == 1).count(),
-        );
-
-        for row in grid.chunks(GRID_LENGTH as usize) {
-            println!("{:?}", row);
+    fn apply_dependency_map(&mut self, tables: &Tables<W>, n: u32) {
+        for cell in tables.dependency_maps[n as usize].ones() {
+            let heat = hca_utils::heat_mut(&mut self.heatmap, cell);
+            if *heat > 0 {
+                *heat -= 1;
+            }
         }
-        println!();
+        *hca_utils::heat_mut(&mut self.heatmap, n) = -1;
     }
-}
 
-impl Default for State {
-    fn default() -> Self {
-        let heatmap = hca_utils::get_initial_heatmap();
-        let heatmap_peaks = hca_utils::get_peaks(&heatmap);
-        Self {
-            heatmap,
-            heatmap_peaks,
-            depth: GRID_SIZE,
+    /**
+     * Display the current state, row by row; with a blank line after each layer.
+     */
+    pub fn print_grid(&self, board: &Board) {
+        let painted_cells = self.painted_cells(board);
+
+        for layer in 0..board.depth {
+            for row in 0..board.height {
+                let row: Vec<i8> = (0..board.width)
+                    .map(|column| painted_cells.test(board.cell([layer, row, column])) as i8)
+                    .collect();
+                println!("{:?}", row);
+            }
+            println!();
         }
     }
-}
-
-/**
- * Print out the heatmap.
- */
-impl std::fmt::Display for State {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut formatted_heatmap = "".to_owned();
 
-        for row in self.heatmap.chunks(GRID_LENGTH as usize) {
-            formatted_heatmap += &format!("{:?}\n", row);
+    /**
+     * Print out the heatmap.
+     */
+    pub fn print_heatmap(&self, board: &Board) {
+        println!("Heatmap:");
+        for layer in 0..board.depth {
+            if layer > 0 {
+                println!();
+            }
+            for row in 0..board.height {
+                let row: Vec<i16> = (0..board.width)
+                    .map(|column| hca_utils::heat(&self.heatmap, board.cell([layer, row, column])))
+                    .collect();
+                println!("{:?}", row);
+            }
         }
-
-        write!(f, "Heatmap:\n{}", formatted_heatmap)
     }
 }
@@ -0,0 +1,37 @@
+use crate::hca_utils;
+
+use a227133_core::symmetry::Symmetries;
+use a227133_core::upper_bound::{upper_bound, UpperBound};
+use a227133_core::{get_squares, Board, Grid};
+
+/**
+ * Everything about the grid that the search looks up, rather than recalculates.
+ * These only depend upon the grid side, so are calculated once; before the search starts.
+ */
+pub struct Tables<const W: usize> {
+    pub board: Board,
+    pub squares: Vec<Grid<W>>,
+    pub dependency_maps: Vec<Grid<W>>,
+    pub symmetries: Symmetries,
+    /**
+     * No grid with more painted cells can be square-free; so the search need not check any above it.
+     */
+    pub upper_bound: UpperBound,
+}
+
+impl<const W: usize> Tables<W> {
+    pub fn new(board: Board) -> Tables<W> {
+        let squares: Vec<Grid<W>> = get_squares(&board);
+        let dependency_maps = hca_utils::get_dependency_maps(&board, &squares);
+        let symmetries = Symmetries::new(&board);
+        let upper_bound = upper_bound(&board, &squares);
+
+        Tables {
+            board,
+            squares,
+            dependency_maps,
+            symmetries,
+            upper_bound,
+        }
+    }
+}
@@ -0,0 +1,350 @@
+/**
+ * Checkpoints of a long-running search, so that a killed run can be resumed rather than restarted.
+ *
+ * A checkpoint records the board, the popcount being searched & how far each shard of that popcount
+ * has got; as the rank of the first permutation it has not yet checked. Every permutation of a shard
+ * before that rank has been fully checked, so resuming from it produces the same final answer.
+ *
+ * The file is plain text, one key=value per line:
+ *
+ * length=7                 (or height=<m> & width=<n>, for a rectangular board; & depth=<l>, for a solid one)
+ * squares=all-orientations (only when the squares are not the axis-parallel ones)
+ * cells=.. & transforms=..  (the spec of the pattern, in place of the squares; see a227133_core::pattern)
+ * topology=torus           (only when the squares wrap across the edges of the board)
+ * popcount=33
+ * elapsed_ms=86400000
+ * shard=<first rank> <end rank> <first unchecked rank>
+ */
+use crate::permutations::number_of_permutation_with_repititions;
+use crate::search::{Level, Shard};
+
+use a227133_core::pattern::Pattern;
+use a227133_core::{Board, SquareFamily, Topology};
+
+use std::fs;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+pub struct Checkpoint {
+    pub board: Board,
+    pub popcount: u32,
+    /**
+     * The time spent searching by every previous run.
+     */
+    pub elapsed: Duration,
+    /**
+     * (first rank, end rank, first unchecked rank) of each shard.
+     */
+    pub shards: Vec<(u128, u128, u128)>,
+}
+
+impl Checkpoint {
+    pub fn read(path: &str) -> Result<Checkpoint, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|error| format!("Could not read checkpoint {}: {}", path, error))?;
+        let invalid = |line: &str| format!("Invalid line in checkpoint {}: '{}'", path, line);
+
+        let (mut height, mut width, mut popcount, mut elapsed) = (None, None, None, None);
+        let mut depth = 1;
+        let mut family = SquareFamily::AxisParallel;
+        let mut pattern_spec = String::new();
+        let mut topology = Topology::Plane;
+        let mut shards: Vec<(u128, u128, u128)> = Vec::new();
+
+        for line in contents.lines().filter(|line| !line.starts_with('#')) {
+            let (key, value) = line.split_once('=').ok_or_else(|| invalid(line))?;
+            match key {
+                "length" => {
+                    height = Some(value.parse().map_err(|_| invalid(line))?);
+                    width = height;
+                }
+                "height" => height = Some(value.parse().map_err(|_| invalid(line))?),
+                "width" => width = Some(value.parse().map_err(|_| invalid(line))?),
+                "depth" => depth = value.parse().map_err(|_| invalid(line))?,
+                "squares" => family = SquareFamily::parse(value).map_err(|_| invalid(line))?,
+                "cells" | "transforms" => pattern_spec += &format!("{}\n", line),
+                "topology" => match value {
+                    "torus" => topology = Topology::Torus,
+                    "plane" => topology = Topology::Plane,
+                    _ => return Err(invalid(line)),
+                },
+                "popcount" => popcount = Some(value.parse().map_err(|_| invalid(line))?),
+                // Written by earlier versions, whose search skipped the non-canonical grids; the ranks are the same:
+                "symmetry" => {}
+                "elapsed_ms" => {
+                    elapsed = Some(Duration::from_millis(
+                        value.parse().map_err(|_| invalid(line))?,
+                    ))
+                }
+                "shard" => {
+                    let ranks: Vec<u128> = value
+                        .split_whitespace()
+                        .map(|rank| rank.parse().map_err(|_| invalid(line)))
+                        .collect::<Result<_, _>>()?;
+                    match ranks[..] {
+                        [first, end, next] if first <= next && next <= end => {
+                            shards.push((first, end, next))
+                        }
+                        _ => return Err(invalid(line)),
+                    }
+                }
+                _ => return Err(invalid(line)),
+            }
+        }
+
+        if !pattern_spec.is_empty() {
+            let pattern = Pattern::parse(&pattern_spec)
+                .map_err(|error| format!("Invalid pattern in checkpoint {}: {}", path, error))?;
+            family = SquareFamily::Pattern(Arc::new(pattern));
+        }
+
+        let missing = |key: &str| format!("Checkpoint {} is missing '{}'.", path, key);
+        let (height, width): (u32, u32) = (
+            height.ok_or_else(|| missing("length"))?,
+            width.ok_or_else(|| missing("width"))?,
+        );
+        let invalid_board =
+            |error: String| format!("Invalid board in checkpoint {}: {}", path, error);
+        // The same bounds on the sides & cells as a board given on the command line:
+        let board = Board::parse(&format!("{}x{}x{}", height, width, depth))
+            .map_err(invalid_board)?
+            .with_family(family)
+            .with_topology(topology);
+        board.validate().map_err(invalid_board)?;
+
+        let checkpoint = Checkpoint {
+            board,
+            popcount: popcount.ok_or_else(|| missing("popcount"))?,
+            elapsed: elapsed.ok_or_else(|| missing("elapsed_ms"))?,
+            shards,
+        };
+        if !checkpoint.shards_cover_the_popcount() {
+            return Err(format!(
+                "The shards of checkpoint {} do not cover every permutation of popcount {}.",
+                path, checkpoint.popcount
+            ));
+        }
+        Ok(checkpoint)
+    }
+
+    /**
+     * Whether the shards cover the ranks [0, C(size, popcount)) in order, with no gaps or overlaps;
+     * otherwise resuming would skip permutations that were never checked.
+     */
+    fn shards_cover_the_popcount(&self) -> bool {
+        let permutation_qty = number_of_permutation_with_repititions(&self.board, self.popcount);
+        let mut covered: u128 = 0;
+        for &(first, end, _) in self.shards.iter() {
+            if first != covered || end <= first {
+                return false;
+            }
+            covered = end;
+        }
+        permutation_qty > 0 && covered == permutation_qty
+    }
+
+    /**
+     * Written to a temporary file & then renamed over the checkpoint,
+     * so that a run killed mid-write never leaves a truncated checkpoint behind.
+     */
+    pub fn write(&self, path: &str) -> Result<(), String> {
+        let mut dimensions = if self.board.is_solid() {
+            format!(
+                "height={}\nwidth={}\ndepth={}",
+                self.board.height, self.board.width, self.board.depth
+            )
+        } else if self.board.is_square() {
+            format!("length={}", self.board.width)
+        } else {
+            format!("height={}\nwidth={}", self.board.height, self.board.width)
+        };
+        match &self.board.family {
+            SquareFamily::AxisParallel => {}
+            SquareFamily::Pattern(pattern) => dimensions += &format!("\n{}", pattern),
+            family => dimensions += &format!("\nsquares={}", family),
+        }
+        if self.board.topology == Topology::Torus {
+            dimensions += "\ntopology=torus";
+        }
+        let mut contents = format!(
+            "# naive_solution checkpoint; resume via --resume {}\n\
+             {}\npopcount={}\nelapsed_ms={}\n",
+            path,
+            dimensions,
+            self.popcount,
+            self.elapsed.as_millis()
+        );
+        for (first, end, next) in self.shards.iter() {
+            contents += &format!("shard={} {} {}\n", first, end, next);
+        }
+
+        let temporary_path = format!("{}.tmp", path);
+        fs::write(&temporary_path, contents)
+            .and_then(|_| fs::rename(&temporary_path, path))
+            .map_err(|error| format!("Could not write checkpoint {}: {}", path, error))
+    }
+
+    /**
+     * The popcount to resume, with each shard starting from its first unchecked permutation.
+     */
+    pub fn level(&self) -> Level {
+        Level {
+            popcount: self.popcount,
+            shards: self
+                .shards
+                .iter()
+                .map(|&(first, end, next)| Shard::new(first, end, next))
+                .collect(),
+        }
+    }
+}
+
+/**
+ * Periodically writes checkpoints of the search to a file.
+ * Called by the shards themselves, so whichever shard first notices that the interval has passed writes it.
+ */
+pub struct Checkpointer {
+    path: String,
+    interval: Duration,
+    board: Board,
+    started: Instant,
+    previous_elapsed: Duration,
+    last_write: Mutex<Instant>,
+}
+
+impl Checkpointer {
+    pub fn new(
+        path: String,
+        interval: Duration,
+        board: Board,
+        previous_elapsed: Duration,
+    ) -> Checkpointer {
+        Checkpointer {
+            path,
+            interval,
+            board,
+            started: Instant::now(),
+            previous_elapsed,
+            last_write: Mutex::new(Instant::now()),
+        }
+    }
+
+    /**
+     * Write a checkpoint if the interval has passed since the last one.
+     * Never blocks a shard: if another shard is already writing, this one carries on searching.
+     */
+    pub fn maybe_write(&self, level: &Level) {
+        if let Ok(mut last_write) = self.last_write.try_lock() {
+            if last_write.elapsed() >= self.interval {
+                self.write_unlocked(level);
+                *last_write = Instant::now();
+            }
+        }
+    }
+
+    pub fn write(&self, level: &Level) {
+        let mut last_write = self.last_write.lock().unwrap();
+        self.write_unlocked(level);
+        *last_write = Instant::now();
+    }
+
+    fn write_unlocked(&self, level: &Level) {
+        let checkpoint = Checkpoint {
+            board: self.board.clone(),
+            popcount: level.popcount,
+            elapsed: self.previous_elapsed + self.started.elapsed(),
+            shards: level
+                .shards
+                .iter()
+                .map(|shard| (shard.first, shard.end, shard.next()))
+                .collect(),
+        };
+        // A failed checkpoint should not kill a search that may have been running for days:
+        if let Err(error) = checkpoint.write(&self.path) {
+            eprintln!("{}", error);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checkpoints_read_back_as_written() {
+        let pattern =
+            Pattern::parse("cells=0,0 0,1 1,0\ntransforms=translation scaling rotation").unwrap();
+        let boards = [
+            Board::new(7),
+            Board::rectangle(4, 6),
+            Board::new(5).with_family(SquareFamily::AllOrientations),
+            Board::rectangle(3, 5).with_family(SquareFamily::Pattern(Arc::new(pattern))),
+            Board::new(6).with_topology(Topology::Torus),
+            Board::cuboid(3, 4, 2).with_family(SquareFamily::Cubes),
+            Board::cuboid(3, 3, 3),
+        ];
+        let path = std::env::temp_dir().join(format!("a227133_checkpoint_{}", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        for (index, board) in boards.into_iter().enumerate() {
+            let popcount = board.size / 2 + index as u32 % 3;
+            let level = Level::new(&board, popcount);
+            let checkpoint = Checkpoint {
+                board,
+                popcount,
+                elapsed: Duration::from_millis(1234),
+                shards: level
+                    .shards
+                    .iter()
+                    .enumerate()
+                    .map(|(shard, range)| {
+                        (
+                            range.first,
+                            range.end,
+                            range.end.min(range.first + shard as u128),
+                        )
+                    })
+                    .collect(),
+            };
+            checkpoint.write(path).unwrap();
+            let read = Checkpoint::read(path).unwrap();
+
+            assert_eq!(read.board, checkpoint.board);
+            assert_eq!(read.popcount, checkpoint.popcount);
+            assert_eq!(read.elapsed, checkpoint.elapsed);
+            assert_eq!(read.shards, checkpoint.shards);
+        }
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn invalid_checkpoints_are_rejected() {
+        let path = std::env::temp_dir().join(format!("a227133_invalid_{}", std::process::id()));
+        let path = path.to_str().unwrap();
+        let header = "length=4\npopcount=12\nelapsed_ms=0\n";
+
+        for contents in [
+            // No shards, a gap, an overlap & a shard short of C(16, 12) = 1820:
+            header.to_string(),
+            format!("{}shard=0 1 1\n", header),
+            format!("{}shard=0 900 900\nshard=901 1820 901\n", header),
+            format!("{}shard=0 900 900\nshard=899 1820 899\n", header),
+            format!("{}shard=0 1819 0\n", header),
+            // Boards too large, empty or without the squares:
+            "length=40\npopcount=12\nelapsed_ms=0\nshard=0 1 0\n".to_string(),
+            "length=0\npopcount=0\nelapsed_ms=0\nshard=0 1 0\n".to_string(),
+            format!("{}squares=cubes\nshard=0 1820 0\n", header),
+        ] {
+            fs::write(path, &contents).unwrap();
+            assert!(Checkpoint::read(path).is_err(), "{}", contents);
+        }
+
+        fs::write(
+            path,
+            format!("{}shard=0 900 12\nshard=900 1820 900\n", header),
+        )
+        .unwrap();
+        assert!(Checkpoint::read(path).is_ok());
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,510 @@
+use a227133_core::grid;
+use a227133_core::local_search::Annealing;
+use a227133_core::pattern::Pattern;
+use a227133_core::{Board, SquareFamily, Topology};
+
+use std::fs;
+use std::sync::Arc;
+use std::time::Duration;
+
+/**
+ * How often a checkpoint is written, unless overridden by --checkpoint-interval.
+ */
+const DEFAULT_CHECKPOINT_INTERVAL: Duration = Duration::from_secs(60);
+
+/**
+ * Which solver finds a(n).
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Solver {
+    /**
+     * Every grid of each popcount, from the highest possible popcount down, via Gosper's hack.
+     */
+    Naive,
+    /**
+     * The depth-first branch-and-bound solver of a227133_core.
+     */
+    BranchAndBound,
+    /**
+     * The CDCL SAT solver of a227133_core; proves each a(n) by unsatisfiability at a(n) + 1.
+     */
+    Sat,
+    /**
+     * The simulated annealing of a227133_core; fast, but only finds a lower bound on a(n).
+     */
+    LocalSearch,
+}
+
+/**
+ * What to do, parsed from the command line.
+ */
+#[derive(Clone, Debug)]
+pub enum Command {
+    Solve(Options),
+    /**
+     * Write the DIMACS CNF for "at least k painted cells & no square".
+     */
+    ExportCnf {
+        board: Board,
+        k: u32,
+        output: Option<String>,
+    },
+    /**
+     * Read back a SAT solver's model of that CNF & validate it.
+     */
+    CheckModel {
+        board: Board,
+        k: u32,
+        model: String,
+    },
+    /**
+     * Check a certificate of optimality written by --certificate.
+     */
+    Verify {
+        certificate: String,
+    },
+    /**
+     * Solve a(m, n) for every m <= n in the triangle of boards up to height × width.
+     */
+    Table {
+        height: u32,
+        width: u32,
+        solver: Solver,
+    },
+}
+
+/**
+ * The options of a run of the naive solver, parsed from the command line.
+ */
+#[derive(Clone, Debug)]
+pub struct Options {
+    /**
+     * The boards to solve, in turn: a single board, or each square board in a range of sides.
+     */
+    pub boards: Vec<Board>,
+    pub solver: Solver,
+    /**
+     * For --all, only collect the grids that are the smallest integer out of all of their rotations & reflections;
+     * the rest of each family is filled in from its images.
+     */
+    pub symmetry: bool,
+    /**
+     * Collect every valid grid of the optimal popcount, rather than stopping at the first.
+     */
+    pub all: bool,
+    /**
+     * Where --all writes the grids; defaults to a227133_<n>_all.txt.
+     */
+    pub output: Option<String>,
+    /**
+     * Where the search periodically writes its progress to.
+     */
+    pub checkpoint: Option<String>,
+    pub checkpoint_interval: Duration,
+    /**
+     * The checkpoint to carry on from; the grid side is taken from it.
+     */
+    pub resume: Option<String>,
+    /**
+     * Where the SAT solver writes the certificate of optimality: its solution & the proof that a(n) + 1 is impossible.
+     */
+    pub certificate: Option<String>,
+    /**
+     * Do not render the progress of the search as a status line on stderr.
+     */
+    pub quiet: bool,
+    /**
+     * Start the exact solver from the grid found by local search; so that it only searches for a better one.
+     */
+    pub warm_start: bool,
+    /**
+     * The seed & length of the local search.
+     */
+    pub annealing: Annealing,
+}
+
+pub const USAGE: &str =
+    "Usage: naive_solution <n> [m] [--all [--no-symmetry] [--output <file>]] [--quiet]
+       naive_solution <m>x<n> [options]
+       naive_solution <m>x<n>x<l> [--squares cubes] [options]
+       naive_solution <n> [m] --squares <axis-parallel|all-orientations> [options]
+       naive_solution <n> [m] --pattern <pattern file> [options]
+       naive_solution <n> [m] --torus [options]
+       naive_solution <n> [m] --solver <naive|branch-and-bound|sat> [--warm-start]
+       naive_solution <n> [m] --solver local-search [--seed <seed>] [--iterations <moves>]
+       naive_solution <n> --solver sat --certificate <file>
+       naive_solution <n> --checkpoint <file> [--checkpoint-interval <seconds>]
+       naive_solution --resume <file> [--checkpoint <file>] [--checkpoint-interval <seconds>]
+       naive_solution export-cnf <board> <k> [--output <file>] [--squares <family> | --pattern <file>] [--torus]
+       naive_solution check-model <board> <k> <model file> [--squares <family> | --pattern <file>] [--torus]
+       naive_solution verify <certificate file>
+       naive_solution table <m> <n> [--solver <branch-and-bound|sat>]";
+
+//---------------------------
+// Command Line Functions:
+//---------------------------
+
+/**
+ * Parse the command line: either a subcommand, or the options of a solve.
+ */
+pub fn parse_command(arguments: &[String]) -> Result<Command, String> {
+    match arguments.first().map(String::as_str) {
+        Some(subcommand @ ("export-cnf" | "check-model")) => {
+            parse_cnf_command(subcommand, &arguments[1..])
+        }
+        Some("verify") => match &arguments[1..] {
+            [certificate] => Ok(Command::Verify {
+                certificate: certificate.clone(),
+            }),
+            _ => Err(USAGE.to_string()),
+        },
+        Some("table") => {
+            let (height, width, solver) = match &arguments[1..] {
+                [m, n] => (m, n, Solver::Sat),
+                [m, n, flag, solver] if flag == "--solver" => match solver.as_str() {
+                    "branch-and-bound" => (m, n, Solver::BranchAndBound),
+                    "sat" => (m, n, Solver::Sat),
+                    other => {
+                        return Err(format!(
+                            "The table is filled by the branch-and-bound or sat solver, not '{}'.",
+                            other
+                        ))
+                    }
+                },
+                _ => return Err(USAGE.to_string()),
+            };
+            // Every board of the table fits within the m×n one:
+            let (height, width) = (parse_side(height)?, parse_side(width)?);
+            if height
+                .checked_mul(width)
+                .is_none_or(|cells| cells > grid::MAX_CELLS)
+            {
+                return Err(format!(
+                    "Invalid table {} × {}: expected m * n <= {}.",
+                    height,
+                    width,
+                    grid::MAX_CELLS
+                ));
+            }
+            Ok(Command::Table {
+                height,
+                width,
+                solver,
+            })
+        }
+        _ => parse_arguments(arguments).map(Command::Solve),
+    }
+}
+
+/**
+ * Parse export-cnf or check-model: the board & k (& the model file, for check-model), with the same square options
+ * as a solve; so that a model is checked against the squares that were exported.
+ */
+fn parse_cnf_command(subcommand: &str, arguments: &[String]) -> Result<Command, String> {
+    let mut positionals: Vec<&str> = Vec::new();
+    let mut output: Option<String> = None;
+    let mut squares = SquareOptions::default();
+
+    let mut arguments = arguments.iter();
+    while let Some(argument) = arguments.next() {
+        match argument.as_str() {
+            flag if squares.parse(flag, &mut arguments)? => {}
+            "--output" if subcommand == "export-cnf" => {
+                output = Some(next_value(&mut arguments, "--output")?)
+            }
+            flag if flag.starts_with("--") => {
+                return Err(format!("Unknown option '{}'.\n{}", flag, USAGE))
+            }
+            positional => positionals.push(positional),
+        }
+    }
+
+    match (subcommand, &positionals[..]) {
+        ("export-cnf", [board, k]) => Ok(Command::ExportCnf {
+            board: squares.apply(Board::parse(board)?)?,
+            k: parse_popcount(k)?,
+            output,
+        }),
+        ("check-model", [board, k, model]) => Ok(Command::CheckModel {
+            board: squares.apply(Board::parse(board)?)?,
+            k: parse_popcount(k)?,
+            model: model.to_string(),
+        }),
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+/**
+ * Parse the options of a solve: either a single board (n, or m×n as <m>x<n>), or an inclusive range of sides n m.
+ * Every side in the range is solved in turn, so one build can regenerate the whole table of terms.
+ */
+pub fn parse_arguments(arguments: &[String]) -> Result<Options, String> {
+    let mut positionals: Vec<&str> = Vec::new();
+    let mut solver = Solver::Naive;
+    let mut symmetry = true;
+    let mut all = false;
+    let mut output: Option<String> = None;
+    let mut checkpoint: Option<String> = None;
+    let mut checkpoint_interval = DEFAULT_CHECKPOINT_INTERVAL;
+    let mut resume: Option<String> = None;
+    let mut certificate: Option<String> = None;
+    let mut quiet = false;
+    let mut warm_start = false;
+    let mut annealing = Annealing::default();
+    let mut annealing_given = false;
+    let mut squares = SquareOptions::default();
+
+    let mut arguments = arguments.iter();
+    while let Some(argument) = arguments.next() {
+        match argument.as_str() {
+            flag if squares.parse(flag, &mut arguments)? => {}
+            "--solver" => {
+                solver = match next_value(&mut arguments, "--solver")?.as_str() {
+                    "naive" => Solver::Naive,
+                    "branch-and-bound" => Solver::BranchAndBound,
+                    "sat" => Solver::Sat,
+                    "local-search" => Solver::LocalSearch,
+                    other => return Err(format!("Unknown solver '{}'.\n{}", other, USAGE)),
+                }
+            }
+            "--no-symmetry" => symmetry = false,
+            "--quiet" => quiet = true,
+            "--all" => all = true,
+            "--output" => output = Some(next_value(&mut arguments, "--output")?),
+            "--checkpoint" => checkpoint = Some(next_value(&mut arguments, "--checkpoint")?),
+            "--checkpoint-interval" => {
+                let seconds = next_value(&mut arguments, "--checkpoint-interval")?;
+                checkpoint_interval = seconds
+                    .parse()
+                    .map(Duration::from_secs)
+                    .map_err(|_| format!("Invalid checkpoint interval '{}'.", seconds))?;
+            }
+            "--resume" => resume = Some(next_value(&mut arguments, "--resume")?),
+            "--certificate" => certificate = Some(next_value(&mut arguments, "--certificate")?),
+            "--warm-start" => warm_start = true,
+            "--seed" => {
+                let seed = next_value(&mut arguments, "--seed")?;
+                annealing.seed = seed
+                    .parse()
+                    .map_err(|_| format!("Invalid seed '{}'.", seed))?;
+                annealing_given = true;
+            }
+            "--iterations" => {
+                let iterations = next_value(&mut arguments, "--iterations")?;
+                annealing.iterations = iterations
+                    .parse()
+                    .map_err(|_| format!("Invalid number of iterations '{}'.", iterations))?;
+                annealing_given = true;
+            }
+            flag if flag.starts_with("--") => {
+                return Err(format!("Unknown option '{}'.\n{}", flag, USAGE))
+            }
+            positional => positionals.push(positional),
+        }
+    }
+
+    // When resuming, the board is filled in from the checkpoint:
+    let boards: Vec<Board> = match positionals[..] {
+        [] if resume.is_some() && squares.is_default() => Vec::new(),
+        _ if resume.is_some() => {
+            return Err(format!(
+                "--resume takes the board, its squares & its topology from the checkpoint.\n{}",
+                USAGE
+            ))
+        }
+        [board] => vec![Board::parse(board)?],
+        [n, m] => {
+            let (n, m) = (parse_length(n)?, parse_length(m)?);
+            if n > m {
+                return Err(format!("Invalid range: {} is greater than {}.", n, m));
+            }
+            (n..=m).map(Board::new).collect()
+        }
+        _ => return Err(USAGE.to_string()),
+    };
+    let boards: Vec<Board> = boards
+        .into_iter()
+        .map(|board| squares.apply(board))
+        .collect::<Result<_, _>>()?;
+    let single_board = boards.len() <= 1;
+
+    if output.is_some() && !all {
+        return Err(format!("--output requires --all.\n{}", USAGE));
+    }
+    if !symmetry && !all {
+        return Err(format!("--no-symmetry requires --all.\n{}", USAGE));
+    }
+    if output.is_some() && !single_board {
+        return Err(format!("--output requires a single board.\n{}", USAGE));
+    }
+    if all && (checkpoint.is_some() || resume.is_some()) {
+        return Err(format!("--all cannot be checkpointed.\n{}", USAGE));
+    }
+    if solver != Solver::Naive && (all || checkpoint.is_some() || resume.is_some()) {
+        return Err(format!(
+            "--all, --checkpoint & --resume require the naive solver.\n{}",
+            USAGE
+        ));
+    }
+    if checkpoint.is_some() && !single_board {
+        return Err(format!("--checkpoint requires a single board.\n{}", USAGE));
+    }
+
+    if certificate.is_some() && (solver != Solver::Sat || !single_board) {
+        return Err(format!(
+            "--certificate requires the sat solver & a single board.\n{}",
+            USAGE
+        ));
+    }
+    if certificate.is_some() && matches!(squares.family, Some(SquareFamily::Pattern(_))) {
+        return Err(format!(
+            "--certificate only covers the square families, not --pattern.\n{}",
+            USAGE
+        ));
+    }
+    if certificate.is_some() && boards.iter().any(Board::is_solid) {
+        return Err(format!(
+            "--certificate only covers plane boards, not solid ones.\n{}",
+            USAGE
+        ));
+    }
+
+    if warm_start && (all || solver == Solver::LocalSearch) {
+        return Err(format!(
+            "--warm-start requires an exact solver, & cannot be combined with --all.\n{}",
+            USAGE
+        ));
+    }
+    if annealing_given && !(warm_start || solver == Solver::LocalSearch) {
+        return Err(format!(
+            "--seed & --iterations require the local-search solver or --warm-start.\n{}",
+            USAGE
+        ));
+    }
+
+    Ok(Options {
+        boards,
+        solver,
+        symmetry,
+        all,
+        output,
+        checkpoint,
+        checkpoint_interval,
+        resume,
+        certificate,
+        quiet,
+        warm_start,
+        annealing,
+    })
+}
+
+/**
+ * The options that choose a board's squares: --squares, --pattern & --torus.
+ */
+#[derive(Default)]
+struct SquareOptions {
+    family: Option<SquareFamily>,
+    topology: Topology,
+}
+
+impl SquareOptions {
+    /**
+     * Take the option (& its value), if it is one of these; returns whether it was.
+     */
+    fn parse<'a>(
+        &mut self,
+        option: &str,
+        arguments: &mut impl Iterator<Item = &'a String>,
+    ) -> Result<bool, String> {
+        match option {
+            "--squares" | "--pattern" if self.family.is_some() => {
+                return Err(format!(
+                    "Only one of --squares & --pattern may be given.\n{}",
+                    USAGE
+                ))
+            }
+            "--squares" => {
+                self.family = Some(SquareFamily::parse(&next_value(arguments, "--squares")?)?)
+            }
+            "--pattern" => {
+                self.family = Some(SquareFamily::Pattern(Arc::new(read_pattern(&next_value(
+                    arguments,
+                    "--pattern",
+                )?)?)))
+            }
+            "--torus" => self.topology = Topology::Torus,
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    fn is_default(&self) -> bool {
+        self.family.is_none() && self.topology == Topology::Plane
+    }
+
+    /**
+     * The board with these squares; which must be defined on it.
+     */
+    fn apply(&self, board: Board) -> Result<Board, String> {
+        let board = board
+            .with_family(self.family.clone().unwrap_or_default())
+            .with_topology(self.topology);
+        board
+            .validate()
+            .map_err(|error| format!("{}\n{}", error, USAGE))?;
+        Ok(board)
+    }
+}
+
+/**
+ * The value following an option that takes one.
+ */
+fn next_value<'a>(
+    arguments: &mut impl Iterator<Item = &'a String>,
+    option: &str,
+) -> Result<String, String> {
+    arguments
+        .next()
+        .cloned()
+        .ok_or(format!("{} expects a value.\n{}", option, USAGE))
+}
+
+fn read_pattern(path: &str) -> Result<Pattern, String> {
+    let spec = fs::read_to_string(path)
+        .map_err(|error| format!("Could not read pattern {}: {}", path, error))?;
+    Pattern::parse(&spec).map_err(|error| format!("{}: {}", path, error))
+}
+
+fn parse_popcount(argument: &str) -> Result<u32, String> {
+    argument
+        .parse()
+        .map_err(|_| format!("Invalid popcount '{}'.", argument))
+}
+
+fn parse_side(argument: &str) -> Result<u32, String> {
+    match argument.parse::<u32>() {
+        Ok(side) if side >= 1 => Ok(side),
+        _ => Err(format!(
+            "Invalid side '{}': expected an integer >= 1.",
+            argument
+        )),
+    }
+}
+
+fn parse_length(argument: &str) -> Result<u32, String> {
+    match argument.parse::<u32>() {
+        Ok(length)
+            if length >= 1
+                && length
+                    .checked_mul(length)
+                    .is_some_and(|cells| cells <= grid::MAX_CELLS) =>
+        {
+            Ok(length)
+        }
+        _ => Err(format!(
+            "Invalid grid side '{}': expected an integer n >= 1 with n * n <= {}.",
+            argument,
+            grid::MAX_CELLS
+        )),
+    }
+}
@@ -0,0 +1,7 @@
+/**
+ * The Gosper's hack search behind the naive_solution binary; a library, so that the other solvers can be
+ * cross-checked against it.
+ */
+pub mod checkpoint;
+pub mod permutations;
+pub mod search;
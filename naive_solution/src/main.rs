@@ -14,191 +14,536 @@
  * The grid contains that square & hence is invalid.
  *
  */
-extern crate rayon;
-use rayon::prelude::*;
+use naive_solution::checkpoint::{Checkpoint, Checkpointer};
 
-extern crate itertools;
-use itertools::iproduct; // used by get_squares.
+mod cli;
+use cli::{Command, Options, Solver};
 
-extern crate unfold;
-use unfold::Unfold; // used for generating candidate grids via Gosper's hack.
+use naive_solution::search::{search, search_all, Level};
 
-use std::char::MAX;
-use std::cmp::min;
-use std::time::Instant;
+extern crate a227133_core;
+use a227133_core::certificate::Certificate;
+use a227133_core::local_search::anneal;
+use a227133_core::progress::{Observer, Reporter, StatusLine, PROGRESS_INTERVAL};
+use a227133_core::symmetry::Symmetries;
+use a227133_core::upper_bound::{upper_bound, UpperBound};
+use a227133_core::{branch_and_bound, cnf, sat};
+use a227133_core::{get_squares, with_grid_words, Board, Grid};
 
-const GRID_LENGTH: u32 = 6;
-const GRID_SIZE: u32 = GRID_LENGTH * GRID_LENGTH;
-const MAX_CHUNK_SIZE: usize = 32000000000; // 4 gigabytes in bits.
+extern crate rayon;
 
-//-----------------------
-// Grid Search Functions:
-//-----------------------
+use std::env;
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::process;
+use std::time::{Duration, Instant};
 
 /**
- * Check all permutations of popcount
- * Recurse to lower popcount if none found.
+ * The observers of a search's progress: the status line, unless it is turned off or stderr is not a terminal.
  */
-fn search(squares: Vec<u128>, popcount: u32) -> u128 {
-    // Build the generator that creates a Vector of gospers_hack permutations for this popcount:
-    let mut generator: Unfold<i128, fn(i128) -> i128> =
-        Unfold::new(gospers_hack, (1 << popcount) - 1);
-
-    // Gosper's hack is sequential, but grid checking can be parallelised since each permutation is indepedent.
-    // Additionally the memory required to store the permuations from Gosper's hack can be in the gigabytes
-    // This means that Gosper's hack permutations can be generated in chunks:
-
-    // Maximum number of candidates that can be taken at once from the generator:
-    let maximum_number_of_candiates: i128 = (MAX_CHUNK_SIZE / 128) as i128;
-    let mut permutation_qty: i128 = number_of_permutation_with_repititions(popcount) as i128;
-    let mut chunk: i128 = min(permutation_qty, maximum_number_of_candiates);
-
-    let mut result: Option<u128> = Option::None;
-
-    // Search all of the permutations, one chunk at a time:
-    while (permutation_qty > 0) {
-        // Generate the candidates:
-        let candidates: Vec<u128> = generator
-            .by_ref()
-            .take(chunk as usize)
-            .map(|permutation| permutation as u128)
-            .collect();
-
-        // Return or recurse to the lexographically prior permutations:
-        match check_candidates(candidates, &squares) {
-            Some(x) => {
-                // Solution found:
-                result = Some(x);
-                permutation_qty = 0;
-            }
-
-            nil => {
-                // Move onto the next chunk:
-                permutation_qty -= chunk;
-                chunk = min(permutation_qty, maximum_number_of_candiates);
-            }
-        }
+fn observers<'a>(options: &Options, status_line: &'a StatusLine) -> Vec<&'a dyn Observer> {
+    if options.quiet || !io::stderr().is_terminal() {
+        Vec::new()
+    } else {
+        vec![status_line]
     }
+}
 
-    // Return or recurse to the lexographically prior permutations:
-    match result {
-        Some(x) => x,
-        nil => search(squares, popcount - 1),
-    }
+/**
+ * The popcount that the search starts from; & the argument that it is due to.
+ */
+fn print_upper_bound(board: &Board, upper_bound: &UpperBound) {
+    println!(
+        "F({}) <= {}, by the {}; searching down from there.",
+        board, upper_bound.value, upper_bound.argument
+    );
 }
 
-fn check_candidates(candidates: Vec<u128>, squares: &[u128]) -> Option<u128> {
-    candidates
-        .par_iter()
-        .find_any(|&&permutation| !grid_contains_squares(permutation, squares))
-        .copied()
+/**
+ * Solve a single grid side & print its result line.
+ * The grid width is picked at runtime, to be the smallest that can hold the board.
+ */
+fn solve(board: &Board, options: &Options, resume_from: Option<&Checkpoint>) {
+    with_grid_words!(board.size, W => solve_with_width::<W>(board, options, resume_from))
 }
 
-// Isolated for the purposes of parallelism:
-fn grid_contains_squares(grid: u128, squares: &[u128]) -> bool {
-    for square in squares {
-        if (grid & square) == *square {
-            return true;
-        }
+/**
+ * Resuming from a checkpoint carries on from the popcount & shard progress that it recorded,
+ * & counts the time spent by the previous runs.
+ */
+fn solve_with_width<const W: usize>(
+    board: &Board,
+    options: &Options,
+    resume_from: Option<&Checkpoint>,
+) {
+    if options.all {
+        return solve_all_with_width::<W>(board, options);
     }
-    false
-}
+    if options.solver == Solver::LocalSearch {
+        return solve_locally_with_width::<W>(board, options);
+    }
+    if options.solver != Solver::Naive {
+        return solve_exactly_with_width::<W>(board, options);
+    }
+    let now = Instant::now();
+
+    let squares: Vec<Grid<W>> = get_squares(board);
+    let incumbent = warm_start(board, &squares, options);
+
+    let (level, previous_elapsed) = match resume_from {
+        Some(checkpoint) => (checkpoint.level(), checkpoint.elapsed),
+        None => {
+            let upper_bound = upper_bound(board, &squares);
+            print_upper_bound(board, &upper_bound);
+            (Level::new(board, upper_bound.value), Duration::ZERO)
+        }
+    };
+    let checkpointer = options
+        .checkpoint
+        .clone()
+        .or(options.resume.clone())
+        .map(|path| {
+            Checkpointer::new(
+                path,
+                options.checkpoint_interval,
+                board.clone(),
+                previous_elapsed,
+            )
+        });
 
-//---------------------------
-// Grid Generation Functions:
-//---------------------------
+    let status_line = StatusLine::default();
+    let reporter = Reporter::new(observers(options, &status_line), PROGRESS_INTERVAL);
+    let solution = search(
+        board,
+        &squares,
+        level,
+        incumbent,
+        checkpointer.as_ref(),
+        &reporter,
+    );
+
+    println!(
+        "F({}) = {} in {:.2?}. Solution: {:0width$b}",
+        board,
+        solution.count_ones(),
+        previous_elapsed + now.elapsed(),
+        solution,
+        width = board.size as usize
+    );
+}
 
 /**
- * This is used to take() the precise number of permutations
- * generated by Gospers hack.
- * Neccessary since the permutations are generated via unfolding,
- * which is an infinite structure.
+ * Solve via one of the exact solvers of a227133_core, rather than the naive search.
  */
-fn number_of_permutation_with_repititions(popcount: u32) -> u128 {
-    let popcount_factorial: u128 = ((popcount + 1)..=GRID_SIZE).map(|x| x as u128).product();
-    let difference_factorial: u128 = (1..=(GRID_SIZE - popcount)).map(|x| x as u128).product();
-    popcount_factorial / difference_factorial
+fn solve_exactly_with_width<const W: usize>(board: &Board, options: &Options) {
+    let now = Instant::now();
+    let incumbent: Grid<W> = warm_start(board, &get_squares(board), options);
+    let solution: Grid<W> = match (options.solver, &options.certificate) {
+        (Solver::BranchAndBound, _) => branch_and_bound::solve_from(board, incumbent),
+        (Solver::Sat, None) => sat::solve_from(board, incumbent),
+        (Solver::Sat, Some(path)) => {
+            let (solution, certificate) = match sat::solve_with_certificate_from(board, incumbent) {
+                Ok(certified) => certified,
+                Err(error) => {
+                    eprintln!("{}", error);
+                    process::exit(1);
+                }
+            };
+            if let Err(error) = fs::write(path, certificate.to_text()) {
+                eprintln!("Could not write {}: {}", path, error);
+                process::exit(1);
+            }
+            println!(
+                "Certificate of {} lemmas written to {}.",
+                certificate.lemmas.len(),
+                path
+            );
+            solution
+        }
+        (Solver::Naive | Solver::LocalSearch, _) => {
+            unreachable!("The naive & local search solvers are run above.")
+        }
+    };
+
+    println!(
+        "F({}) = {} in {:.2?}. Solution: {:0width$b}",
+        board,
+        solution.count_ones(),
+        now.elapsed(),
+        solution,
+        width = board.size as usize
+    );
 }
 
 /**
- * This is a bit hack algorithm by Bill Gosper:
- * It generates the candidate grids.
- * Generates the lexographically next permutation given a permutation as input.
- * Initial input is (1 << popcount) - 1
+ * Only a lower bound: print each grid that the local search improves to, as it is found, & then the best.
  */
-fn gospers_hack(permutation: i128) -> i128 {
-    let c: i128 = permutation & -permutation;
-    let r = permutation + c;
+fn solve_locally_with_width<const W: usize>(board: &Board, options: &Options) {
+    let now = Instant::now();
+    let squares: Vec<Grid<W>> = get_squares(board);
+    let solution = anneal(board, &squares, &options.annealing, |grid| {
+        println!(
+            "F({}) >= {} after {:.2?}.",
+            board,
+            grid.count_ones(),
+            now.elapsed()
+        );
+    });
 
-    (((r ^ permutation) >> 2) / c) | r
+    println!(
+        "F({}) >= {} in {:.2?}; a lower bound, from local search with seed {}. Solution: {:0width$b}",
+        board,
+        solution.count_ones(),
+        now.elapsed(),
+        options.annealing.seed,
+        solution,
+        width = board.size as usize
+    );
 }
 
-//-----------------------------
-// Square Generation Functions:
-//-----------------------------
+/**
+ * Given --warm-start, the grid found by local search; so that the exact solver only searches for a better one.
+ * Otherwise the empty grid, which every grid beats.
+ */
+fn warm_start<const W: usize>(board: &Board, squares: &[Grid<W>], options: &Options) -> Grid<W> {
+    if !options.warm_start {
+        return Grid::empty();
+    }
+    let incumbent = anneal(board, squares, &options.annealing, |_| {});
+    println!(
+        "F({}) >= {}, by local search; searching for a better grid.",
+        board,
+        incumbent.count_ones()
+    );
+    incumbent
+}
+
+/**
+ * Collect every optimal grid, print how many there are (both in total & up to rotation/reflection),
+ * & write them to a file; one grid per line.
+ */
+fn solve_all_with_width<const W: usize>(board: &Board, options: &Options) {
+    let now = Instant::now();
+
+    let squares: Vec<Grid<W>> = get_squares(board);
+    let symmetries = Symmetries::new(board);
+    let upper_bound = upper_bound(board, &squares);
+    print_upper_bound(board, &upper_bound);
+    let status_line = StatusLine::default();
+    let reporter = Reporter::new(observers(options, &status_line), PROGRESS_INTERVAL);
+    let solutions = every_optimal_grid(
+        board,
+        &squares,
+        &symmetries,
+        options.symmetry,
+        upper_bound.value,
+        &reporter,
+    );
+
+    // Each family is counted once, via its canonical grid:
+    let (total_qty, family_qty) = (
+        solutions.len(),
+        solutions
+            .iter()
+            .filter(|solution| symmetries.is_canonical(*solution))
+            .count(),
+    );
+
+    let path = options
+        .output
+        .clone()
+        .unwrap_or(format!("a227133_{}_all.txt", board_label(board)));
+    let mut contents = format!(
+        "# F({}) = {}: {} grids, {} up to rotation & reflection.\n",
+        board,
+        solutions[0].count_ones(),
+        total_qty,
+        family_qty
+    );
+    for solution in solutions.iter() {
+        contents += &format!("{:0width$b}\n", solution, width = board.size as usize);
+    }
+    if let Err(error) = fs::write(&path, contents) {
+        eprintln!("Could not write {}: {}", path, error);
+        process::exit(1);
+    }
+
+    println!(
+        "F({}) = {} in {:.2?}. {} optimal grids, {} up to rotation & reflection; written to {}.",
+        board,
+        solutions[0].count_ones(),
+        now.elapsed(),
+        total_qty,
+        family_qty,
+        path
+    );
+}
 
 /**
- * The possible squares in the grid can be pre-calculated
- * These can then be checked against a candidate_grid via bitwise anding
+ * Every optimal grid, in ascending order. Given symmetry, only the canonical grid of each family is searched for,
+ * & the rest of the family is then filled in from its images.
  */
-fn get_squares() -> Vec<u128> {
-    let squares: Vec<u128> = iproduct!(1..=GRID_SIZE, 2..=GRID_LENGTH)
-        .filter(|(index, scale)| valid_square(*index, *scale))
-        .map(|(index, scale)| construct_square(index, scale))
+fn every_optimal_grid<const W: usize>(
+    board: &Board,
+    squares: &[Grid<W>],
+    symmetries: &Symmetries,
+    symmetry: bool,
+    popcount: u32,
+    reporter: &Reporter,
+) -> Vec<Grid<W>> {
+    let solutions = search_all(
+        board,
+        squares,
+        symmetry.then_some(symmetries),
+        popcount,
+        reporter,
+    );
+    if !symmetry {
+        return solutions;
+    }
+    let mut solutions: Vec<Grid<W>> = solutions
+        .iter()
+        .flat_map(|solution| symmetries.family(solution))
         .collect();
-    squares
+    solutions.sort();
+    solutions
 }
 
 /**
- * A square is an integer of entirely zeroes, except for 4 set bits,
- * These 4 set bits are the corners of the square.
+ * How a board is named on the command line & in file names: n, <m>x<n> or <m>x<n>x<l>.
  */
-const fn construct_square(top_left_corner_index: u32, scale: u32) -> u128 {
-    let square: u128 = u128::pow(2, top_left_corner_index - 1)
-        + u128::pow(2, top_left_corner_index - 1 + scale - 1)
-        + u128::pow(2, top_left_corner_index - 1 + (GRID_LENGTH * (scale - 1)))
-        + u128::pow(
-            2,
-            top_left_corner_index - 1 + (GRID_LENGTH * (scale - 1)) + scale - 1,
-        );
-    square
+fn board_label(board: &Board) -> String {
+    if board.is_solid() {
+        format!("{}x{}x{}", board.height, board.width, board.depth)
+    } else if board.is_square() {
+        board.width.to_string()
+    } else {
+        format!("{}x{}", board.height, board.width)
+    }
 }
 
-//------------------------------------
-// Square Generation Helper Functions:
-//------------------------------------
+/**
+ * Print the triangle of a(m, n) for m <= n, up to height × width; each term as soon as it is solved.
+ * a(m, n) = a(n, m), so the boards with more rows than columns are left blank.
+ */
+fn table(height: u32, width: u32, solver: Solver) {
+    print!("{:>7}", "m \\ n");
+    for n in 1..=width {
+        print!("{:>5}", n);
+    }
+    println!();
 
-pub fn valid_square(index: u32, scale: u32) -> bool {
-    square_within_bounds(index, scale) & !edge_is_on_different_row(index, scale)
+    for m in 1..=height {
+        print!("{:>7}", m);
+        for n in 1..=width {
+            if n < m {
+                print!("{:>5}", "");
+                continue;
+            }
+            let board = Board::rectangle(m, n);
+            let term = with_grid_words!(board.size, W => {
+                let solution: Grid<W> = match solver {
+                    Solver::BranchAndBound => branch_and_bound::solve(&board),
+                    _ => sat::solve(&board),
+                };
+                solution.count_ones()
+            });
+            print!("{:>5}", term);
+            let _ = io::stdout().flush();
+        }
+        println!();
+    }
 }
 
-fn get_current_row(index: u32) -> u32 {
-    ((index - 1) + GRID_LENGTH - ((index - 1) % GRID_LENGTH)) / GRID_LENGTH
+/**
+ * Write the CNF to the file, or to stdout when no file is given.
+ */
+fn export_cnf(board: Board, k: u32, output: Option<&String>) -> Result<(), String> {
+    let dimacs = with_grid_words!(board.size, W => {
+        let squares: Vec<Grid<W>> = get_squares(&board);
+        cnf::encode(&board, &squares, k).to_dimacs()
+    });
+
+    match output {
+        Some(path) => {
+            fs::write(path, dimacs).map_err(|error| format!("Could not write {}: {}", path, error))
+        }
+        None => {
+            print!("{}", dimacs);
+            Ok(())
+        }
+    }
 }
 
-fn edge_is_on_different_row(index: u32, scale: u32) -> bool {
-    (get_current_row(index + scale - 1) - get_current_row(index)) > 0
+fn check_model(board: Board, k: u32, model: &str) -> Result<(), String> {
+    let contents = fs::read_to_string(model)
+        .map_err(|error| format!("Could not read {}: {}", model, error))?;
+
+    with_grid_words!(board.size, W => {
+        let squares: Vec<Grid<W>> = get_squares(&board);
+        let grid: Grid<W> = cnf::read_model(&board, &contents)?;
+        cnf::validate_model(grid, &squares, k)?;
+        println!(
+            "Valid: {} painted cells & no square. Solution: {:0width$b}",
+            grid.count_ones(),
+            grid,
+            width = board.size as usize
+        );
+        Ok(())
+    })
 }
 
-fn square_within_bounds(index: u32, scale: u32) -> bool {
-    (index + (scale - 1) + GRID_LENGTH * (scale - 1)) <= GRID_SIZE
+/**
+ * Replay a certificate against a freshly generated list of squares.
+ */
+fn verify(path: &str) -> Result<(), String> {
+    let contents =
+        fs::read_to_string(path).map_err(|error| format!("Could not read {}: {}", path, error))?;
+    let certificate = Certificate::read(&contents)?;
+    let term = certificate.verify()?;
+    println!(
+        "Verified: F({}) = {}; a grid with {} painted cells & no square, & a proof of {} lemmas that none has {}.",
+        certificate.board(),
+        term,
+        term,
+        certificate.lemmas.len(),
+        certificate.k
+    );
+    Ok(())
 }
 
 //---------------
 // Main Function:
 //---------------
 fn main() {
-    let now = Instant::now();
+    let arguments: Vec<String> = env::args().skip(1).collect();
 
-    let squares: Vec<u128> = get_squares();
-    let solution = search(squares, GRID_SIZE - GRID_LENGTH + 1);
+    let exit_with = |error: String| -> ! {
+        eprintln!("{}", error);
+        process::exit(1);
+    };
+    let mut options = match cli::parse_command(&arguments) {
+        Ok(Command::Solve(options)) => options,
+        Ok(Command::ExportCnf { board, k, output }) => {
+            return export_cnf(board, k, output.as_ref()).unwrap_or_else(|error| exit_with(error))
+        }
+        Ok(Command::CheckModel { board, k, model }) => {
+            return check_model(board, k, &model).unwrap_or_else(|error| exit_with(error))
+        }
+        Ok(Command::Table {
+            height,
+            width,
+            solver,
+        }) => return table(height, width, solver),
+        Ok(Command::Verify { certificate }) => {
+            return verify(&certificate).unwrap_or_else(|error| exit_with(error))
+        }
+        Err(error) => exit_with(error),
+    };
 
-    println!(
-        "F({}) = {} in {:.2?}.\nSolution: {:0width$b}",
-        GRID_LENGTH,
-        solution.count_ones(),
-        now.elapsed(),
-        solution,
-        width = GRID_SIZE as usize
-    );
+    let resume_from: Option<Checkpoint> = options
+        .resume
+        .as_ref()
+        .map(|path| Checkpoint::read(path).unwrap_or_else(|error| exit_with(error)));
+    if let Some(checkpoint) = &resume_from {
+        options.boards = vec![checkpoint.board.clone()];
+    }
+
+    for board in options.boards.iter() {
+        solve(board, &options, resume_from.as_ref());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use a227133_core::SquareFamily;
+
+    fn arguments(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+
+    fn write_model(path: &str, grid: Grid<1>) {
+        let literals: Vec<String> = grid.ones().map(|cell| (cell + 1).to_string()).collect();
+        fs::write(path, format!("s SATISFIABLE\nv {} 0\n", literals.join(" "))).unwrap();
+    }
+
+    #[test]
+    fn export_cnf_and_check_model_take_the_square_options() {
+        let path = env::temp_dir().join(format!("a227133_cnf_{}", process::id()));
+        let path = path.to_str().unwrap();
+
+        let tilted = Board::new(4).with_family(SquareFamily::AllOrientations);
+        let tilted_optimum: Grid<1> = sat::solve(&tilted);
+        let k = tilted_optimum.count_ones();
+
+        let command = format!(
+            "export-cnf 4 {} --squares all-orientations --output {}",
+            k, path
+        );
+        let Ok(Command::ExportCnf { board, k, output }) = cli::parse_command(&arguments(&command))
+        else {
+            panic!("export-cnf should parse");
+        };
+        assert_eq!(board.family, SquareFamily::AllOrientations);
+        export_cnf(board, k, output.as_ref()).unwrap();
+        let squares: Vec<Grid<1>> = get_squares(&tilted);
+        assert_eq!(
+            fs::read_to_string(path).unwrap(),
+            cnf::encode(&tilted, &squares, k).to_dimacs()
+        );
+
+        let check = |k: u32, options: &str| {
+            let command = format!("check-model 4 {} {} {}", k, path, options);
+            match cli::parse_command(&arguments(&command)) {
+                Ok(Command::CheckModel { board, k, model }) => check_model(board, k, &model),
+                _ => panic!("check-model should parse"),
+            }
+        };
+
+        write_model(path, tilted_optimum);
+        assert!(check(k, "--squares all-orientations").is_ok());
+
+        let axis_parallel_optimum: Grid<1> = sat::solve(&Board::new(4));
+        assert!(axis_parallel_optimum.count_ones() > k);
+        write_model(path, axis_parallel_optimum);
+        assert!(check(k, "").is_ok());
+        assert!(check(k, "--squares all-orientations").is_err());
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn every_optimal_grid_is_found_with_or_without_symmetry() {
+        for (board, total_qty, family_qty) in
+            [(Board::new(5), 204, 27), (Board::rectangle(3, 5), 20, 5)]
+        {
+            let squares: Vec<Grid<1>> = get_squares(&board);
+            let symmetries = Symmetries::new(&board);
+            let popcount = upper_bound(&board, &squares).value;
+            let all = |symmetry: bool| {
+                every_optimal_grid(
+                    &board,
+                    &squares,
+                    &symmetries,
+                    symmetry,
+                    popcount,
+                    &Reporter::silent(),
+                )
+            };
+            let (pruned, unpruned) = (all(true), all(false));
+
+            assert_eq!(pruned, unpruned, "{:?}", board);
+            assert_eq!(pruned.len(), total_qty, "{:?}", board);
+            assert_eq!(
+                pruned
+                    .iter()
+                    .filter(|grid| symmetries.is_canonical(*grid))
+                    .count(),
+                family_qty,
+                "{:?}",
+                board
+            );
+        }
+    }
 }
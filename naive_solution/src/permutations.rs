@@ -0,0 +1,132 @@
+use a227133_core::{Board, Grid};
+
+use std::cmp::min;
+
+//---------------------------
+// Grid Generation Functions:
+//---------------------------
+
+/**
+ * The number of permutations generated by Gospers hack for a popcount;
+ * used to split them into equally sized shards.
+ *
+ * Computed as a running binomial coefficient, so that no factorial is ever stored;
+ * saturates at u128::MAX for the grids too large to ever be fully enumerated.
+ */
+pub fn number_of_permutation_with_repititions(board: &Board, popcount: u32) -> u128 {
+    binomial(board.size, popcount)
+}
+
+/**
+ * This is a bit hack algorithm by Bill Gosper:
+ * It generates the candidate grids.
+ * Generates the lexographically next permutation given a permutation as input.
+ * Initial input is Grid::with_lowest_bits(popcount); see Grid::next_combination.
+ */
+pub fn gospers_hack<const W: usize>(permutation: Grid<W>) -> Grid<W> {
+    permutation.next_combination()
+}
+
+//-------------------------------
+// Ranking & Unranking Functions:
+//-------------------------------
+
+/**
+ * The position of a permutation in the sequence generated by Gosper's hack for its popcount;
+ * Grid::with_lowest_bits(popcount) has rank 0.
+ *
+ * Via the combinatorial number system: the set bits c_k > ... > c_1 of a permutation
+ * are at position sum(C(c_i, i)) in the sequence.
+ * Used to split the search into shards, to resume from a position & to report progress.
+ */
+pub fn rank<const W: usize>(permutation: Grid<W>) -> u128 {
+    permutation
+        .ones()
+        .zip(1..)
+        .map(|(position, i)| binomial(position, i))
+        .sum()
+}
+
+/**
+ * The permutation that Gosper's hack would generate index steps after Grid::with_lowest_bits(popcount);
+ * the inverse of rank.
+ *
+ * From the highest set bit down, each bit is placed at the largest c_i for which
+ * C(c_i, i) still fits into the remaining index.
+ */
+pub fn unrank<const W: usize>(index: u128, popcount: u32) -> Grid<W> {
+    let mut permutation = Grid::empty();
+    let mut remaining = index;
+    let mut position = Grid::<W>::BITS;
+
+    for i in (1..=popcount).rev() {
+        position -= 1;
+        while binomial(position, i) > remaining {
+            position -= 1;
+        }
+        permutation.set(position);
+        remaining -= binomial(position, i);
+    }
+    permutation
+}
+
+/**
+ * C(n, k), saturating at u128::MAX.
+ */
+fn binomial(n: u32, k: u32) -> u128 {
+    if k > n {
+        return 0;
+    }
+    let k = min(k, n - k) as u128;
+    (0..k).fold(1, |coefficient: u128, i| {
+        coefficient
+            .checked_mul(n as u128 - i)
+            .map(|product| product / (i + 1))
+            .unwrap_or(u128::MAX)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unrank_inverts_rank() {
+        let board = Board::new(4);
+        for popcount in 0..=board.size {
+            let permutation_qty = number_of_permutation_with_repititions(&board, popcount);
+            for index in (0..permutation_qty).step_by(97) {
+                let permutation: Grid<1> = unrank(index, popcount);
+                assert_eq!(permutation.count_ones(), popcount);
+                assert_eq!(rank(permutation), index);
+                assert_eq!(unrank::<1>(rank(permutation), popcount), permutation);
+            }
+        }
+    }
+
+    #[test]
+    fn ranks_follow_gospers_hack() {
+        let board = Board::new(4);
+        for popcount in 1..=board.size {
+            let mut permutation: Grid<1> = Grid::with_lowest_bits(popcount);
+            for index in 0..number_of_permutation_with_repititions(&board, popcount) {
+                assert_eq!(rank(permutation), index);
+                assert_eq!(unrank::<1>(index, popcount), permutation);
+                permutation = gospers_hack(permutation);
+            }
+        }
+    }
+
+    #[test]
+    fn ranks_follow_gospers_hack_across_words() {
+        // Start just below the boundary between the first & second word:
+        let mut permutation: Grid<2> = unrank(binomial(63, 3) - 50, 3);
+        let first = rank(permutation);
+        for index in first..(first + 10_000) {
+            let next = gospers_hack(permutation);
+            assert!(next > permutation);
+            assert_eq!(rank(next), index + 1);
+            permutation = next;
+        }
+    }
+}
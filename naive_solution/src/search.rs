@@ -0,0 +1,277 @@
+use crate::checkpoint::Checkpointer;
+use crate::permutations::{gospers_hack, number_of_permutation_with_repititions, unrank};
+
+use a227133_core::progress::{Progress, Reporter};
+use a227133_core::symmetry::Symmetries;
+use a227133_core::{grid_contains_squares, Board, Grid};
+
+use rayon::prelude::*;
+
+use std::cmp::min;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+/**
+ * Each popcount is split into this many shards per thread;
+ * more shards than threads keeps the workers balanced once the quicker shards finish.
+ */
+const SHARDS_PER_THREAD: u128 = 8;
+
+/**
+ * How many permutations a shard checks between looking to see if another shard has found a solution;
+ * & between recording its progress.
+ */
+const CANCELLATION_INTERVAL: u128 = 1 << 12;
+
+/**
+ * A range of ranks [first, end) of the permutations of a popcount,
+ * & the rank of the first permutation in it that has not yet been checked.
+ */
+pub struct Shard {
+    pub first: u128,
+    pub end: u128,
+    next: Mutex<u128>,
+}
+
+impl Shard {
+    pub fn new(first: u128, end: u128, next: u128) -> Shard {
+        Shard {
+            first,
+            end,
+            next: Mutex::new(next),
+        }
+    }
+
+    pub fn next(&self) -> u128 {
+        *self.next.lock().unwrap()
+    }
+
+    fn record_progress(&self, next: u128) {
+        *self.next.lock().unwrap() = next;
+    }
+}
+
+/**
+ * All of the permutations of a popcount, split into shards.
+ */
+pub struct Level {
+    pub popcount: u32,
+    pub shards: Vec<Shard>,
+}
+
+impl Level {
+    /**
+     * Split the permutations of popcount into K equal ranges of ranks.
+     */
+    pub fn new(board: &Board, popcount: u32) -> Level {
+        let permutation_qty: u128 = number_of_permutation_with_repititions(board, popcount);
+        let shard_qty: u128 = min(
+            permutation_qty,
+            rayon::current_num_threads() as u128 * SHARDS_PER_THREAD,
+        );
+
+        // Shard i covers the ranks [shard_start(i), shard_start(i + 1)):
+        let shard_start = |shard: u128| -> u128 {
+            (permutation_qty / shard_qty) * shard + min(shard, permutation_qty % shard_qty)
+        };
+
+        Level {
+            popcount,
+            shards: (0..shard_qty)
+                .map(|shard| {
+                    Shard::new(
+                        shard_start(shard),
+                        shard_start(shard + 1),
+                        shard_start(shard),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    /**
+     * How many of the level's permutations have been checked, out of how many.
+     */
+    pub fn progress(&self) -> Progress {
+        Progress {
+            popcount: self.popcount,
+            checked: self
+                .shards
+                .iter()
+                .map(|shard| shard.next() - shard.first)
+                .sum(),
+            total: Some(
+                self.shards
+                    .iter()
+                    .map(|shard| shard.end - shard.first)
+                    .sum(),
+            ),
+            ..Progress::default()
+        }
+    }
+}
+
+//-----------------------
+// Grid Search Functions:
+//-----------------------
+
+/**
+ * Check all permutations of popcount
+ * Move on to the next lower popcount if none found.
+ *
+ * The first square-free grid is returned, whichever of its family it is; so no symmetries are used here.
+ * They could not shorten the sweep: a symmetry relates the high bits to the low ones (a reflection swaps the
+ * first & last rows), so no run of Gosper's order can be skipped by its leading bits alone.
+ *
+ * Gosper's hack is sequential, but the permutations of a popcount can be split into K equal ranges;
+ * each range is seeded with its first permutation (via unrank), so that K generators can run in parallel.
+ * Generation & grid checking are fused in each worker, so no permutations are ever stored.
+ *
+ * Given a checkpointer, the progress of every shard is periodically written to disk;
+ * a level read back from a checkpoint carries on from where its shards had got to.
+ * The reporter is sent the start & end of each level, & is periodically sent the progress through it.
+ *
+ * Given a (square-free) incumbent, the levels at or below its popcount are never checked;
+ * once every level above it is found to be empty, the incumbent is returned.
+ */
+pub fn search<const W: usize>(
+    board: &Board,
+    squares: &[Grid<W>],
+    mut level: Level,
+    incumbent: Grid<W>,
+    checkpointer: Option<&Checkpointer>,
+    reporter: &Reporter,
+) -> Grid<W> {
+    loop {
+        if incumbent.count_ones() >= level.popcount {
+            return incumbent;
+        }
+        if let Some(checkpointer) = checkpointer {
+            checkpointer.write(&level);
+        }
+        reporter.start_level(level.progress());
+
+        let solution_found = AtomicBool::new(false);
+        let result: Option<Grid<W>> = level.shards.par_iter().find_map_any(|shard| {
+            check_shard(
+                shard,
+                &level,
+                squares,
+                &solution_found,
+                checkpointer,
+                reporter,
+            )
+        });
+        reporter.finish_level(level.progress());
+
+        // Return or move on to the lexographically prior permutations:
+        match result {
+            Some(x) => return x,
+            None => level = Level::new(board, level.popcount - 1),
+        }
+    }
+}
+
+/**
+ * Like search, but every valid grid of the highest possible popcount is collected rather than the first.
+ * Given symmetries, only the canonical grid of each family of rotations & reflections is collected.
+ */
+pub fn search_all<const W: usize>(
+    board: &Board,
+    squares: &[Grid<W>],
+    symmetries: Option<&Symmetries>,
+    mut popcount: u32,
+    reporter: &Reporter,
+) -> Vec<Grid<W>> {
+    loop {
+        let level = Level::new(board, popcount);
+        reporter.start_level(level.progress());
+
+        let mut solutions: Vec<Grid<W>> = level
+            .shards
+            .par_iter()
+            .flat_map_iter(|shard| collect_shard(shard, &level, squares, symmetries, reporter))
+            .collect();
+        reporter.finish_level(level.progress());
+
+        if !solutions.is_empty() {
+            solutions.sort();
+            return solutions;
+        }
+        popcount -= 1;
+    }
+}
+
+/**
+ * Check the unchecked permutations of the shard, stepping via Gosper's hack.
+ * Gives up early once any other shard has found a solution.
+ */
+fn check_shard<const W: usize>(
+    shard: &Shard,
+    level: &Level,
+    squares: &[Grid<W>],
+    solution_found: &AtomicBool,
+    checkpointer: Option<&Checkpointer>,
+    reporter: &Reporter,
+) -> Option<Grid<W>> {
+    let next = shard.next();
+    let mut permutation: Grid<W> = unrank(next, level.popcount);
+
+    for rank in next..shard.end {
+        if (rank - next).is_multiple_of(CANCELLATION_INTERVAL) {
+            if solution_found.load(Ordering::Relaxed) {
+                return None;
+            }
+            shard.record_progress(rank);
+            if let Some(checkpointer) = checkpointer {
+                checkpointer.maybe_write(level);
+            }
+            reporter.maybe_report(|| level.progress());
+        }
+        if !grid_contains_squares(permutation, squares) {
+            solution_found.store(true, Ordering::Relaxed);
+            return Some(permutation);
+        }
+        permutation = gospers_hack(permutation);
+    }
+    shard.record_progress(shard.end);
+    None
+}
+
+/**
+ * Check every permutation of the shard, stepping via Gosper's hack.
+ * Collects every one that is a solution.
+ */
+fn collect_shard<const W: usize>(
+    shard: &Shard,
+    level: &Level,
+    squares: &[Grid<W>],
+    symmetries: Option<&Symmetries>,
+    reporter: &Reporter,
+) -> Vec<Grid<W>> {
+    let mut solutions: Vec<Grid<W>> = Vec::new();
+    let mut permutation: Grid<W> = unrank(shard.first, level.popcount);
+
+    for rank in shard.first..shard.end {
+        if (rank - shard.first).is_multiple_of(CANCELLATION_INTERVAL) {
+            shard.record_progress(rank);
+            reporter.maybe_report(|| level.progress());
+        }
+        if is_solution(permutation, squares, symmetries) {
+            solutions.push(permutation);
+        }
+        permutation = gospers_hack(permutation);
+    }
+    shard.record_progress(shard.end);
+    solutions
+}
+
+fn is_solution<const W: usize>(
+    permutation: Grid<W>,
+    squares: &[Grid<W>],
+    symmetries: Option<&Symmetries>,
+) -> bool {
+    // The square check rejects almost every grid within a few squares, so it is run first:
+    !grid_contains_squares(permutation, squares)
+        && symmetries.is_none_or(|symmetries| symmetries.is_canonical(&permutation))
+}